@@ -1,3 +1,5 @@
+use crate::tunnel::SshTunnel;
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -10,35 +12,397 @@ pub(crate) struct Entry {
     dbtype:   String,
     #[serde(default)]
     database: String,
+    /// Connection options (see [`check_option`]), sent as URL parameters.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    options: BTreeMap<String, String>,
+    /// Jump host the database is reached through.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ssh: Option<SshTunnel>,
 }
 
 impl Entry {
-    pub fn to_connection_url(&self) -> String {
-        let db = if self.database.is_empty() {
-            &self.username   
-        } else {
-            &self.database
+    pub(crate) fn new(name: String, ip: String, port: u16, username: String, password: String, dbtype: String, database: String) -> Self {
+        Self { name, ip, port, username, password, dbtype, database, options: BTreeMap::new(), ssh: None }
+    }
+
+    pub(crate) fn with_ssh(mut self, ssh: Option<SshTunnel>) -> Self {
+        self.ssh = ssh;
+        self
+    }
+
+    fn ssh_passphrase(&self) -> Option<&str> {
+        self.ssh.as_ref()?.passphrase.as_deref()
+    }
+
+    /// The jump host to connect through, with `${VAR}` references expanded
+    /// and the key passphrase resolved, and the database host and port to
+    /// forward to; `None` for a direct connection.
+    fn resolved_ssh(&self) -> Result<Option<(SshTunnel, String, u16)>, String> {
+        let Some(ssh) = &self.ssh else { return Ok(None) };
+        let context = |e: String| format!("Saved server '{}': {e}", self.name);
+        let resolved = Entry { ip: expand_vars(&self.ip).map_err(context)?, ..self.clone() };
+        // A socket is forwarded to by its file path.
+        let (target, port) = match resolved.socket() {
+            Some((dir, port)) if !resolved.is_mysql() => (format!("{dir}/.s.PGSQL.{port}"), port),
+            Some(socket) => socket,
+            None => (resolved.ip, self.port),
+        };
+        let ssh = SshTunnel {
+            host: expand_vars(&ssh.host).map_err(context)?,
+            user: expand_vars(&ssh.user).map_err(context)?,
+            key: ssh.key.as_deref().map(expand_vars).transpose().map_err(context)?,
+            passphrase: ssh.passphrase.as_deref().map(crate::secrets::resolve).transpose().map_err(context)?,
+            ..ssh.clone()
+        };
+        Ok(Some((ssh, target, port)))
+    }
+
+    fn is_mysql(&self) -> bool {
+        matches!(self.dbtype.as_str(), "mysql" | "mariadb")
+    }
+
+    /// For an entry whose `ip` is a Unix socket path, the path as the driver
+    /// takes it and the port. PostgreSQL takes the socket's directory and
+    /// names the file after the port, so a full `.s.PGSQL.<port>` path is
+    /// split into the two.
+    fn socket(&self) -> Option<(String, u16)> {
+        if !self.ip.starts_with('/') {
+            return None;
+        }
+        if !self.is_mysql() {
+            if let Some((dir, file)) = self.ip.rsplit_once('/') {
+                if let Some(port) = file.strip_prefix(".s.PGSQL.").and_then(|p| p.parse().ok()) {
+                    return Some((if dir.is_empty() { "/" } else { dir }.to_string(), port));
+                }
+            }
+        }
+        Some((self.ip.clone(), self.port))
+    }
+
+    /// Sets the connection options, rejecting unknown ones.
+    pub(crate) fn with_options(mut self, options: BTreeMap<String, String>) -> Result<Self, String> {
+        self.options = options;
+        self.check_options()?;
+        Ok(self)
+    }
+
+    fn check_options(&self) -> Result<(), String> {
+        self.options.iter().try_for_each(|(k, v)| check_option(&self.dbtype, k, v))
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn host(&self) -> &str {
+        &self.ip
+    }
+
+    pub(crate) fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub(crate) fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Builds the URL to connect with: the password resolved from its source
+    /// or decrypted, and `${VAR}` references in host, user and database
+    /// expanded. The result is used for the connection and never stored.
+    pub fn to_connection_url(&self) -> Result<String, String> {
+        let context = |e: String| format!("Saved server '{}': {e}", self.name);
+        let resolved = Entry {
+            ip: expand_vars(&self.ip).map_err(context)?,
+            database: expand_vars(&self.database).map_err(context)?,
+            password: crate::secrets::resolve(&self.password).map_err(context)?,
+            // MySQL clients log in as the OS user when none is given (as
+            // PostgreSQL's do), which is what socket peer authentication wants.
+            username: match expand_vars(&self.username).map_err(context)? {
+                user if user.is_empty() && self.is_mysql() => crate::discover::os_user(),
+                user => user,
+            },
+            options: self
+                .options
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), expand_vars(v)?)))
+                .collect::<Result<_, String>>()
+                .map_err(context)?,
+            ..self.clone()
+        };
+        Ok(resolved.url_with_password(&percent_encode(&resolved.password)))
+    }
+
+    /// Builds the URL with the password masked, safe to show.
+    pub fn redacted_url(&self) -> String {
+        self.url_with_password("****")
+    }
+
+    /// `password` must already be percent-encoded.
+    fn url_with_password(&self, password: &str) -> String {
+        let scheme = match self.dbtype.as_str() {
+            "mysql" | "mariadb" => "mysql",
+            "postgres" | "postgresql" => "postgres",
+            other => other,
+        };
+        let mysql = scheme == "mysql";
+        let mut params: Vec<(String, String)> = Vec::new();
+        let (host, port) = match self.socket() {
+            // The socket goes in a parameter and the host part is unused.
+            Some((path, port)) => {
+                params.push((if mysql { "socket" } else { "host" }.to_string(), path));
+                ("localhost".to_string(), port)
+            }
+            // IPv6 literals are stored bare and bracketed only in the URL.
+            None if self.ip.contains(':') && !self.ip.starts_with('[') => (format!("[{}]", self.ip), self.port),
+            None => (self.ip.clone(), self.port),
+        };
+        let credentials = match (self.username.is_empty(), password.is_empty()) {
+            (true, true) => String::new(),
+            (_, true) => format!("{}@", percent_encode(&self.username)),
+            _ => format!("{}:{password}@", percent_encode(&self.username)),
+        };
+        let mut url = format!("{scheme}://{credentials}{host}:{port}");
+        if !self.database.is_empty() {
+            url.push('/');
+            url.push_str(&percent_encode(&self.database));
+        }
+        params.extend(self.options.iter().map(|(k, v)| url_option(mysql, k, v)));
+        if !params.is_empty() {
+            let query: Vec<String> =
+                params.iter().map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v))).collect();
+            url.push('?');
+            url.push_str(&query.join("&"));
+        }
+        url
+    }
+
+    /// Reads a `mysql://` or `postgres://` URL into an entry, decoding
+    /// user, password and database and filling in the default port.
+    /// URL parameters become options, under their canonical names. Building
+    /// the URL back with [`Entry::to_connection_url`] gives an equivalent URL.
+    pub(crate) fn from_url(name: String, url: &str) -> Result<Self, String> {
+        let parsed = url::Url::parse(url).map_err(|e| format!("Invalid connection string: {e}"))?;
+        let dbtype = match parsed.scheme() {
+            "mysql" | "mariadb" => "mysql",
+            "postgres" | "postgresql" => "postgres",
+            _ => return Err("Invalid connection string. Must start with mysql:// or postgres://".into()),
+        };
+        let mut ip = match parsed.host() {
+            Some(url::Host::Ipv6(addr)) => addr.to_string(),
+            Some(host) if !host.to_string().is_empty() => {
+                percent_decode(&host.to_string()).ok_or("Invalid host in connection string.")?
+            }
+            // The drivers connect to localhost when no host is given.
+            _ => "localhost".to_string(),
+        };
+        let decode = |part: &str, what: &str| {
+            percent_decode(part).ok_or_else(|| format!("Invalid {what} in connection string."))
         };
+        let database = parsed.path().trim_start_matches('/');
+        if database.contains('/') {
+            return Err(format!("Invalid database name '{database}' in connection string."));
+        }
+        let mut options = BTreeMap::new();
+        for (k, v) in parsed.query_pairs() {
+            match (dbtype, k.as_ref()) {
+                // A socket path takes the place of the host.
+                ("postgres", "host") | ("mysql", "socket") if v.starts_with('/') => ip = v.into_owned(),
+                _ => {
+                    let (k, v) = canonical_option(dbtype == "mysql", &k, &v);
+                    options.insert(k, v);
+                }
+            }
+        }
+        Ok(Self {
+            name,
+            ip,
+            port: parsed.port().or(default_port(dbtype)).unwrap_or_default(),
+            username: decode(parsed.username(), "user name")?,
+            password: decode(parsed.password().unwrap_or(""), "password")?,
+            dbtype: dbtype.to_string(),
+            database: decode(database, "database name")?,
+            options,
+            ssh: None,
+        })
+    }
+}
+
+/// Adds connection options to a URL as driver parameters, replacing any
+/// the URL already sets.
+pub fn with_url_options(url: &str, options: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut parsed = url::Url::parse(url).map_err(|e| format!("Invalid connection string: {e}"))?;
+    let mysql = matches!(parsed.scheme(), "mysql" | "mariadb");
+    let dbtype = if mysql { "mysql" } else { "postgres" };
+    options.iter().try_for_each(|(k, v)| check_option(dbtype, k, v))?;
+    let mut pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(k, v)| !options.contains_key(&canonical_option(mysql, k, v).0))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    pairs.extend(options.iter().map(|(k, v)| url_option(mysql, k, v)));
+    parsed.query_pairs_mut().clear().extend_pairs(&pairs);
+    Ok(parsed.to_string())
+}
+
+/// Options an entry may carry: TLS mode and certificate paths, the
+/// PostgreSQL application name, the MySQL character set, a connect timeout
+/// in seconds and the pool settings (see `db::PoolSettings`). Values may
+/// contain `${VAR}` references.
+pub(crate) fn check_option(dbtype: &str, key: &str, value: &str) -> Result<(), String> {
+    let mysql = matches!(dbtype, "mysql" | "mariadb");
+    match key {
+        "sslmode" => {
+            let modes: &[&str] = if mysql {
+                &["disable", "prefer", "require", "verify-ca", "verify-full"]
+            } else {
+                &["disable", "allow", "prefer", "require", "verify-ca", "verify-full"]
+            };
+            if modes.contains(&value) || value.contains("${") {
+                Ok(())
+            } else {
+                Err(format!("Option 'sslmode' must be one of: {}.", modes.join(", ")))
+            }
+        }
+        "sslrootcert" | "sslcert" | "sslkey" => Ok(()),
+        "application_name" if mysql => Err("Option 'application_name' is only supported on PostgreSQL.".into()),
+        "charset" if !mysql => Err("Option 'charset' is only supported on MySQL.".into()),
+        "application_name" | "charset" => Ok(()),
+        "connect_timeout" | "acquire_timeout" | "idle_timeout" | "max_lifetime" | "max_connections"
+        | "min_connections" | "test_before_acquire"
+            if value.contains("${") =>
+        {
+            Ok(())
+        }
+        "connect_timeout" | "acquire_timeout" => match value.parse::<u64>() {
+            Ok(n) if n > 0 => Ok(()),
+            _ => Err(format!("Option '{key}' must be a positive number of seconds.")),
+        },
+        "idle_timeout" | "max_lifetime" => match value.parse::<u64>() {
+            Ok(_) => Ok(()),
+            _ => Err(format!("Option '{key}' must be a number of seconds (0 turns it off).")),
+        },
+        "max_connections" => match value.parse::<u32>() {
+            Ok(n) if n > 0 => Ok(()),
+            _ => Err("Option 'max_connections' must be a positive number.".into()),
+        },
+        "min_connections" => match value.parse::<u32>() {
+            Ok(_) => Ok(()),
+            _ => Err("Option 'min_connections' must be a number.".into()),
+        },
+        "test_before_acquire" => match value {
+            "true" | "false" => Ok(()),
+            _ => Err("Option 'test_before_acquire' must be true or false.".into()),
+        },
+        other => Err(format!(
+            "Unknown option '{other}'. Supported: sslmode, sslrootcert, sslcert, sslkey, application_name, charset, \
+             connect_timeout, max_connections, min_connections, acquire_timeout, idle_timeout, max_lifetime, \
+             test_before_acquire."
+        )),
+    }
+}
+
+const MYSQL_SSL_MODES: [(&str, &str); 5] = [
+    ("disable", "DISABLED"),
+    ("prefer", "PREFERRED"),
+    ("require", "REQUIRED"),
+    ("verify-ca", "VERIFY_CA"),
+    ("verify-full", "VERIFY_IDENTITY"),
+];
+
+/// The URL parameter the driver expects for an option.
+fn url_option(mysql: bool, key: &str, value: &str) -> (String, String) {
+    if !mysql {
+        return (key.to_string(), value.to_string());
+    }
+    match key {
+        "sslmode" => {
+            let mode = MYSQL_SSL_MODES.iter().find(|(ours, _)| *ours == value).map_or(value, |(_, theirs)| theirs);
+            ("ssl-mode".to_string(), mode.to_string())
+        }
+        "sslrootcert" => ("ssl-ca".to_string(), value.to_string()),
+        "sslcert" => ("ssl-cert".to_string(), value.to_string()),
+        "sslkey" => ("ssl-key".to_string(), value.to_string()),
+        _ => (key.to_string(), value.to_string()),
+    }
+}
+
+/// The option a URL parameter sets, under the name `check_option` uses;
+/// parameters with no such option are kept as they are.
+pub(crate) fn canonical_option(mysql: bool, key: &str, value: &str) -> (String, String) {
+    let key = match key {
+        "ssl-mode" | "sslmode" | "ssl_mode" => "sslmode",
+        "ssl-root-cert" | "ssl-ca" | "sslca" | "ssl_ca" => "sslrootcert",
+        "ssl-cert" | "ssl_cert" => "sslcert",
+        "ssl-key" | "ssl_key" => "sslkey",
+        "default_character_set" if mysql => "charset",
+        other => other,
+    };
+    let value = if key == "sslmode" && mysql {
+        let upper = value.to_ascii_uppercase();
+        MYSQL_SSL_MODES
+            .iter()
+            .find(|(_, theirs)| *theirs == upper)
+            .map_or(value.to_string(), |(ours, _)| ours.to_string())
+    } else {
+        value.to_string()
+    };
+    (key.to_string(), value)
+}
+
+/// The server's standard port for a `dbtype`.
+pub(crate) fn default_port(dbtype: &str) -> Option<u16> {
+    match dbtype {
+        "mysql" | "mariadb" => Some(3306),
+        "postgres" | "postgresql" => Some(5432),
+        _ => None,
+    }
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters, so the
+/// value can sit in any part of a URL.
+pub(crate) fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
 
-        match self.dbtype.as_str() {
-            "mysql" | "mariadb" => format!(
-                "mysql://{}:{}@{}:{}/{}",
-                self.username, self.password, self.ip, self.port, db
-            ),
-            "postgres" | "postgresql" => format!(
-                "postgres://{}:{}@{}:{}/{}",
-                self.username, self.password, self.ip, self.port, db
-            ),
-            other => format!(
-                "{}://{}:{}@{}:{}/{}",
-                other, self.username, self.password, self.ip, self.port, db
-            ),
+/// Decodes `%XX` escapes; a `%` not followed by two hex digits is kept as
+/// is. `None` if the result is not UTF-8.
+pub(crate) fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(b) = value.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
         }
+        decoded.push(bytes[i]);
+        i += 1;
     }
+    String::from_utf8(decoded).ok()
 }
 
 pub struct Config {
     config_map: std::collections::HashMap<String, Entry>,
+    /// Names of entries found by `discover` (not saved), with their source.
+    discovered: std::collections::BTreeMap<String, String>,
+    /// Connections from the project's `.db-mcp/connections.toml`. They take
+    /// precedence over same-named discovered entries, but not over saved
+    /// ones, and are never saved.
+    project: Vec<crate::project::ProjectEntry>,
+    /// Names `connect_database` registered for the session only, with
+    /// whether that connection was opened read-only.
+    session: std::collections::HashMap<String, bool>,
 }
 
 fn config_file_path() -> std::path::PathBuf {
@@ -51,7 +415,7 @@ fn config_file_path() -> std::path::PathBuf {
     }
 }
 
-fn home_dir() -> Option<std::path::PathBuf> {
+pub(crate) fn home_dir() -> Option<std::path::PathBuf> {
     std::env::var_os("HOME")
         .or_else(|| std::env::var_os("USERPROFILE"))
         .map(std::path::PathBuf::from)
@@ -59,10 +423,16 @@ fn home_dir() -> Option<std::path::PathBuf> {
 
 impl Config {
     pub fn new() -> Self {
-        Self { config_map: std::collections::HashMap::new() }
+        Self {
+            config_map: std::collections::HashMap::new(),
+            discovered: std::collections::BTreeMap::new(),
+            project: Vec::new(),
+            session: std::collections::HashMap::new(),
+        }
     }
 
-    pub fn add_entry(&mut self, name: String, ip: String, port: u16, username: String, password: String, dbtype: String, database: String,) {
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_entry(&mut self, name: String, ip: String, port: u16, username: String, password: String, dbtype: String, database: String, options: BTreeMap<String, String>) {
         let entry = Entry {
             name: name.clone(),
             ip,
@@ -71,7 +441,10 @@ impl Config {
             password,
             dbtype,
             database,
+            options,
+            ssh: None,
         };
+        self.session.remove(&name);
         self.config_map.insert(name, entry);
     }
 
@@ -79,13 +452,121 @@ impl Config {
         self.config_map.get(name)
     }
 
-    pub fn get_connection_url(&self, name: &str) -> Option<String> {
-        self.config_map.get(name).map(|e| e.to_connection_url())
+    /// Whether `name` is in `~/.db-mcp/config.json` (not only discovered or
+    /// registered for the session).
+    fn is_saved(&self, name: &str) -> bool {
+        self.config_map.contains_key(name) && !self.session.contains_key(name) && !self.discovered.contains_key(name)
+    }
+
+    /// The project connection of that name, unless a saved server has it: a
+    /// cloned repository must not redirect a name the user configured.
+    fn project_entry(&self, name: &str) -> Option<&crate::project::ProjectEntry> {
+        self.project.iter().find(|p| p.entry.name == name).filter(|_| !self.is_saved(name))
+    }
+
+    /// The entry `connect_database` uses for a saved name: the project's,
+    /// else the global one.
+    fn lookup(&self, name: &str) -> Option<&Entry> {
+        self.project_entry(name).map(|p| &p.entry).or_else(|| self.config_map.get(name))
+    }
+
+    /// Whether the project marks this saved name read-only, or it names a
+    /// session entry registered by a read-only connection (so reconnecting
+    /// under that name cannot lift the policy).
+    pub fn is_read_only(&self, name: &str) -> bool {
+        self.project_entry(name).is_some_and(|p| p.read_only)
+            || self.session.get(name).copied().unwrap_or(false)
+    }
+
+    /// Replaces the project connections with those from `files`.
+    pub fn set_project(&mut self, files: &[std::path::PathBuf]) -> Result<usize, String> {
+        self.project = crate::project::load(files)?;
+        for p in self.project.iter().filter(|p| self.is_saved(&p.entry.name)) {
+            eprintln!(
+                "db-mcp: warning — '{}' defines '{}', which is a saved server; the saved one is used.",
+                p.file.display(),
+                p.entry.name
+            );
+        }
+        Ok(self.project.len())
+    }
+
+    fn saved(&self, name: &str) -> Result<Entry, String> {
+        self.lookup(name).cloned().ok_or_else(|| format!(
+            "No saved connection found with name '{name}'. \
+             Use configure_server to save one first."
+        ))
+    }
+
+    /// Offers connections from the standard client files as session-only
+    /// entries. A saved entry with the same name wins.
+    pub fn add_discovered(&mut self, found: Vec<crate::discover::Discovered>) {
+        for d in found {
+            if !self.config_map.contains_key(&d.entry.name) {
+                self.discovered.insert(d.entry.name.clone(), d.source);
+                self.config_map.insert(d.entry.name.clone(), d.entry);
+            }
+        }
+    }
+
+    /// Saves discovered connections (all, or those in `names`) to the config
+    /// file. Existing saved names are left alone unless `overwrite` is set.
+    pub fn import_connections(&mut self, names: &[String], overwrite: bool) -> std::io::Result<serde_json::Value> {
+        let mut found = crate::discover::discover();
+        for name in names {
+            if !found.iter().any(|d| &d.entry.name == name) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No discovered connection named '{name}'. Use list_saved_serverconfig to see them."),
+                ));
+            }
+        }
+        if !names.is_empty() {
+            found.retain(|d| names.contains(&d.entry.name));
+        }
+        let mut sealed = Vec::new();
+        for mut d in found {
+            d.entry.password = seal(std::mem::take(&mut d.entry.password))?;
+            sealed.push(d);
+        }
+
+        let (imported, skipped) = update_file(|entries| {
+            let mut imported = Vec::new();
+            let mut skipped = Vec::new();
+            for d in sealed {
+                match entries.iter_mut().find(|e| e.name == d.entry.name) {
+                    Some(_) if !overwrite => skipped.push(d.entry.name),
+                    Some(existing) => {
+                        *existing = d.entry.clone();
+                        imported.push((d.entry, d.source));
+                    }
+                    None => {
+                        entries.push(d.entry.clone());
+                        imported.push((d.entry, d.source));
+                    }
+                }
+            }
+            Ok((imported, skipped))
+        })?;
+        let mut report = Vec::new();
+        for (entry, source) in imported {
+            report.push(serde_json::json!({ "name": entry.name, "source": source }));
+            self.discovered.remove(&entry.name);
+            self.session.remove(&entry.name);
+            self.config_map.insert(entry.name.clone(), entry);
+        }
+        Ok(serde_json::json!({
+            "imported": report,
+            "skipped_existing": skipped,
+            "config_file": config_file_path().display().to_string()
+        }))
     }
 
     pub fn list_entry_names(&self) -> Vec<String> {
         let mut names: Vec<String> = self.config_map.keys().cloned().collect();
+        names.extend(self.project.iter().map(|p| p.entry.name.clone()));
         names.sort();
+        names.dedup();
         names
     }
 
@@ -99,6 +580,7 @@ impl Config {
             Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
             Err(e) => return Err(e),
         };
+        restrict_permissions(&path)?;
 
         let reader = BufReader::new(file);
         for line in reader.lines() {
@@ -116,51 +598,351 @@ impl Config {
                 }
             }
         }
-        Ok(())
-    }
-
-    fn append_to_file(&self, entry: &Entry) -> std::io::Result<()> {
-        use std::fs::{self, OpenOptions};
-        use std::io::Write;
-
-        let path = config_file_path();
-
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+        let plaintext = self
+            .config_map
+            .values()
+            .filter(|e| is_plaintext(&e.password) || e.ssh_passphrase().is_some_and(is_plaintext))
+            .count();
+        if plaintext > 0 {
+            eprintln!(
+                "db-mcp: {plaintext} saved password(s) in '{}' are stored in plaintext; \
+                 set DB_MCP_PASSPHRASE or DB_MCP_KEY_FILE and run encrypt_saved_passwords.",
+                path.display()
+            );
         }
-
-        let json_line = serde_json::to_string(entry)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&path)?;
-
-        file.write_all(json_line.as_bytes())?;
-        file.write_all(b"\n")?;
         Ok(())
     }
 
-    pub fn configure_server(&mut self, name: String, ip: String, port: u16, username: String, password: String, dbtype: String, database: String,) -> std::io::Result<String> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn configure_server(&mut self, name: String, ip: String, port: u16, username: String, password: String, dbtype: String, database: String, options: BTreeMap<String, String>, mut ssh: Option<SshTunnel>) -> std::io::Result<String> {
+        options
+            .iter()
+            .try_for_each(|(k, v)| check_option(&dbtype, k, v))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        let password = seal(password)?;
+        let plaintext = is_plaintext(&password);
+        if let Some(ssh) = ssh.as_mut() {
+            ssh.passphrase = ssh.passphrase.take().map(seal).transpose()?;
+        }
         self.add_entry(
-            name.clone(), ip, port, username, password, dbtype, database,
+            name.clone(), ip, port, username, password, dbtype, database, options,
         );
+        if let Some(entry) = self.config_map.get_mut(&name) {
+            entry.ssh = ssh;
+        }
         if let Some(entry) = self.get_entry(&name).cloned() {
-            self.append_to_file(&entry)?;
+            update_file(|entries| {
+                match entries.iter_mut().find(|e| e.name == entry.name) {
+                    Some(existing) => *existing = entry,
+                    None => entries.push(entry),
+                }
+                Ok(())
+            })?;
             Ok(format!(
-                "Server '{}' configured and saved to '{}'.",
+                "Server '{}' configured and saved to '{}'.{}",
                 name,
-                config_file_path().display()
+                config_file_path().display(),
+                if plaintext {
+                    " The password is stored in plaintext; set DB_MCP_PASSPHRASE or DB_MCP_KEY_FILE to encrypt it."
+                } else {
+                    ""
+                }
             ))
         } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
+            Err(std::io::Error::other(
                 "Failed to retrieve entry after insertion",
             ))
         }
     }
 
+    pub fn remove_server(&mut self, name: &str) -> std::io::Result<String> {
+        update_file(|entries| {
+            let before = entries.len();
+            entries.retain(|e| e.name != name);
+            if entries.len() == before {
+                return Err(not_saved(name));
+            }
+            Ok(())
+        })?;
+        self.config_map.remove(name);
+        Ok(format!("Server '{}' removed from '{}'.", name, config_file_path().display()))
+    }
+
+    pub fn update_server(&mut self, name: &str, mut changes: EntryUpdate) -> std::io::Result<String> {
+        if let Some(dbtype) = changes.dbtype.as_deref().filter(|t| default_port(t).is_none()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unknown dbtype '{dbtype}'; use mysql, mariadb, postgres or postgresql."),
+            ));
+        }
+        changes.password = changes.password.map(seal).transpose()?;
+        changes.ssh_passphrase = changes.ssh_passphrase.map(seal).transpose()?;
+        let updated = update_file(|entries| {
+            let entry = entries.iter_mut().find(|e| e.name == name).ok_or_else(|| not_saved(name))?;
+            if let Some(ip) = changes.ip { entry.ip = ip; }
+            if let Some(port) = changes.port { entry.port = port; }
+            if let Some(username) = changes.username { entry.username = username; }
+            if let Some(password) = changes.password { entry.password = password; }
+            if let Some(dbtype) = changes.dbtype { entry.dbtype = dbtype; }
+            if let Some(database) = changes.database { entry.database = database; }
+            for (key, value) in changes.options {
+                match value {
+                    Some(value) => entry.options.insert(key, value),
+                    None => entry.options.remove(&key),
+                };
+            }
+            entry
+                .check_options()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            match changes.ssh_host.as_deref() {
+                Some("") => entry.ssh = None,
+                Some(host) => {
+                    let ssh = entry.ssh.get_or_insert_with(|| SshTunnel {
+                        host: String::new(),
+                        port: 22,
+                        user: String::new(),
+                        key: None,
+                        passphrase: None,
+                    });
+                    ssh.host = host.to_string();
+                }
+                None => {}
+            }
+            let ssh_fields = changes.ssh_port.is_some()
+                || changes.ssh_user.is_some()
+                || changes.ssh_key.is_some()
+                || changes.ssh_passphrase.is_some();
+            match entry.ssh.as_mut() {
+                Some(ssh) => {
+                    if let Some(port) = changes.ssh_port { ssh.port = port; }
+                    if let Some(user) = changes.ssh_user { ssh.user = user; }
+                    if let Some(key) = changes.ssh_key { ssh.key = (!key.is_empty()).then_some(key); }
+                    if let Some(passphrase) = changes.ssh_passphrase {
+                        ssh.passphrase = (!passphrase.is_empty()).then_some(passphrase);
+                    }
+                }
+                None if ssh_fields => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Saved server '{name}' has no SSH tunnel; pass ssh_host to add one."),
+                    ));
+                }
+                None => {}
+            }
+            Ok(entry.clone())
+        })?;
+        self.config_map.insert(name.to_string(), updated);
+        Ok(format!("Server '{}' updated in '{}'.", name, config_file_path().display()))
+    }
+
+    pub fn rename_server(&mut self, name: &str, new_name: &str) -> std::io::Result<String> {
+        if name == new_name {
+            if !read_file_entries(&config_file_path())?.iter().any(|e| e.name == name) {
+                return Err(not_saved(name));
+            }
+            return Ok(format!("Server '{name}' is already named '{new_name}'; nothing to do."));
+        }
+        let renamed = update_file(|entries| {
+            if entries.iter().any(|e| e.name == new_name) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("A saved server named '{new_name}' already exists."),
+                ));
+            }
+            let entry = entries.iter_mut().find(|e| e.name == name).ok_or_else(|| not_saved(name))?;
+            entry.name = new_name.to_string();
+            Ok(entry.clone())
+        })?;
+        self.config_map.remove(name);
+        self.session.remove(new_name);
+        self.config_map.insert(new_name.to_string(), renamed);
+        Ok(format!("Server '{}' renamed to '{}'.", name, new_name))
+    }
+
+    /// Encrypts every saved password that is still in plaintext.
+    pub fn encrypt_passwords(&mut self) -> std::io::Result<String> {
+        let source = crate::secrets::key_source().ok_or_else(|| {
+            std::io::Error::other("No encryption key configured. Set DB_MCP_PASSPHRASE or DB_MCP_KEY_FILE.")
+        })?;
+        let entries = update_file(|entries| {
+            for entry in entries.iter_mut() {
+                if is_plaintext(&entry.password) {
+                    entry.password = seal(std::mem::take(&mut entry.password))?;
+                }
+                if let Some(ssh) = entry.ssh.as_mut() {
+                    ssh.passphrase = ssh.passphrase.take().map(seal).transpose()?;
+                }
+            }
+            Ok(entries.clone())
+        })?;
+        let mut migrated = 0;
+        for entry in entries {
+            if self
+                .config_map
+                .get(&entry.name)
+                .is_some_and(|e| e.password != entry.password || e.ssh_passphrase() != entry.ssh_passphrase())
+            {
+                migrated += 1;
+            }
+            self.config_map.insert(entry.name.clone(), entry);
+        }
+        Ok(format!(
+            "Encrypted {migrated} saved password(s) in '{}' with the key from {source}.",
+            config_file_path().display()
+        ))
+    }
+
+}
+
+/// Fields to change on a saved server; `None` keeps the current value.
+#[derive(Default)]
+pub struct EntryUpdate {
+    pub ip:       Option<String>,
+    pub port:     Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub dbtype:   Option<String>,
+    pub database: Option<String>,
+    /// Options to set; `None` removes one.
+    pub options:  BTreeMap<String, Option<String>>,
+    /// Jump host fields; an empty `ssh_host` removes the tunnel.
+    pub ssh_host:       Option<String>,
+    pub ssh_port:       Option<u16>,
+    pub ssh_user:       Option<String>,
+    pub ssh_key:        Option<String>,
+    pub ssh_passphrase: Option<String>,
+}
+
+/// Replaces each `${VAR}` with the variable's value.
+fn expand_vars(value: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| format!("Unterminated '${{' in '{value}'."))?;
+        let var = &rest[start + 2..start + end];
+        out.push_str(&std::env::var(var).map_err(|_| format!("Environment variable '{var}' is not set."))?);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Whether a saved password is a literal held in plaintext (not encrypted,
+/// not a reference to where it comes from).
+fn is_plaintext(password: &str) -> bool {
+    !password.is_empty() && !crate::secrets::is_encrypted(password) && !crate::secrets::is_reference(password)
+}
+
+/// Encrypts a literal password for storage when a key is configured; without
+/// one, and for `env:` / `file:` / `cmd:` references, it is stored as given.
+fn seal(password: String) -> std::io::Result<String> {
+    if !is_plaintext(&password) || crate::secrets::key_source().is_none() {
+        return Ok(password);
+    }
+    crate::secrets::encrypt(&password).map_err(std::io::Error::other)
+}
+
+/// Makes the config file readable by its owner only.
+fn restrict_permissions(path: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path)?.permissions().mode() & 0o777;
+        if mode != 0o600 {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+            eprintln!("db-mcp: tightened permissions of '{}' from {mode:o} to 600", path.display());
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+fn not_saved(name: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, format!("No saved server named '{name}'."))
+}
+
+/// Reads the saved entries, keeping the last line for a repeated name (older
+/// versions appended a new line on every `configure_server`).
+fn read_file_entries(path: &std::path::Path) -> std::io::Result<Vec<Entry>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut entries: Vec<Entry> = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        match serde_json::from_str::<Entry>(line) {
+            Ok(entry) => match entries.iter_mut().find(|e| e.name == entry.name) {
+                Some(existing) => *existing = entry,
+                None => entries.push(entry),
+            },
+            Err(e) => {
+                // Refuse to rewrite a file we could not fully read; it would
+                // silently drop the line.
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("'{}' has a malformed line ({e}); fix it by hand first.", path.display()),
+                ));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Applies `f` to the saved entries and rewrites the config file. The whole
+/// read-modify-write runs under an exclusive lock on a sidecar lock file, and
+/// the new contents replace the old with a rename, so concurrent db-mcp
+/// processes neither lose each other's changes nor see a half-written file.
+fn update_file<T>(f: impl FnOnce(&mut Vec<Entry>) -> std::io::Result<T>) -> std::io::Result<T> {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    let path = config_file_path();
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(parent, fs::Permissions::from_mode(0o700))?;
+            }
+        }
+    }
+    let mut lock_path = path.clone().into_os_string();
+    lock_path.push(".lock");
+    let lock = OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)?;
+    lock.lock()?;
+
+    let mut entries = read_file_entries(&path)?;
+    let result = f(&mut entries)?;
+
+    let mut tmp_path = path.clone().into_os_string();
+    tmp_path.push(format!(".tmp.{}", std::process::id()));
+    let tmp_path = std::path::PathBuf::from(tmp_path);
+    let written = (|| {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut tmp = options.open(&tmp_path)?;
+        for entry in &entries {
+            let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+            tmp.write_all(line.as_bytes())?;
+            tmp.write_all(b"\n")?;
+        }
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, &path)
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    written?;
+    Ok(result)
 }
 
 static CONFIG_INSTANCE: std::sync::OnceLock<Arc<Mutex<Config>>> =
@@ -171,6 +953,12 @@ pub fn initialize_config() -> Result<(), String> {
     if let Err(e) = cfg.load_from_file() {
         eprintln!("db-mcp: warning — could not load config file: {e}");
     }
+    if !matches!(std::env::var("DB_MCP_DISCOVER").as_deref(), Ok("0" | "false")) {
+        cfg.add_discovered(crate::discover::discover());
+    }
+    if let Err(e) = cfg.set_project(&crate::project::project_files(&[])) {
+        eprintln!("db-mcp: warning — could not load project connections: {e}");
+    }
     let _ = CONFIG_INSTANCE.set(Arc::new(Mutex::new(cfg)));
     Ok(())
 }
@@ -186,6 +974,7 @@ where
     f(&mut cfg)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn add_permanent_entry(
     name:     String,
     ip:       String,
@@ -194,27 +983,68 @@ pub fn add_permanent_entry(
     password: String,
     dbtype:   String,
     database: String,
+    options:  BTreeMap<String, String>,
+    ssh:      Option<SshTunnel>,
 ) -> Result<String, String> {
     with_config(|cfg| {
-        cfg.configure_server(name, ip, port, username, password, dbtype, database)
+        cfg.configure_server(name, ip, port, username, password, dbtype, database, options, ssh)
             .map_err(|e| e.to_string())
     })
 }
 
-pub fn add_temporary_entry(name: String, ip: String, port: u16, username: String, password: String, dbtype: String, database: String,
-) -> Result<String, String> {
+/// Registers a connection for the session under `name`. A saved, discovered
+/// or project entry of that name is left as it is, so its password source
+/// is resolved afresh on the next connect rather than replaced by the value
+/// it resolved to this time.
+pub fn add_temporary_entry(name: String, entry: Entry, read_only: bool) -> Result<String, String> {
     with_config(|cfg| {
-        cfg.add_entry(name.clone(), ip, port, username, password, dbtype, database);
+        let saved = cfg.config_map.contains_key(&name) && !cfg.session.contains_key(&name);
+        if saved || cfg.project_entry(&name).is_some() {
+            return Ok(format!("Connection '{name}' uses the saved entry."));
+        }
+        cfg.session.insert(name.clone(), read_only);
+        cfg.config_map.insert(name.clone(), Entry { name: name.clone(), ..entry });
         Ok(format!("Connection '{}' registered (session only).", name))
     })
 }
 
-pub fn get_connection_url(name: &str) -> Option<String> {
-    CONFIG_INSTANCE
-        .get()?
-        .lock()
-        .ok()?
-        .get_connection_url(name)
+pub fn remove_permanent_entry(name: &str) -> Result<String, String> {
+    with_config(|cfg| cfg.remove_server(name).map_err(|e| e.to_string()))
+}
+
+pub fn update_permanent_entry(name: &str, changes: EntryUpdate) -> Result<String, String> {
+    with_config(|cfg| cfg.update_server(name, changes).map_err(|e| e.to_string()))
+}
+
+pub fn rename_permanent_entry(name: &str, new_name: &str) -> Result<String, String> {
+    with_config(|cfg| cfg.rename_server(name, new_name).map_err(|e| e.to_string()))
+}
+
+pub fn encrypt_saved_passwords() -> Result<String, String> {
+    with_config(|cfg| cfg.encrypt_passwords().map_err(|e| e.to_string()))
+}
+
+/// The entry behind a saved name as stored (password and passphrase still
+/// references or encrypted), with `options` added: what a connection opened
+/// from it is registered as.
+pub fn saved_entry(name: &str, options: &BTreeMap<String, String>) -> Result<Entry, String> {
+    with_config(|cfg| {
+        let mut entry = cfg.lookup(name).cloned().ok_or_else(|| format!("No saved connection found with name '{name}'."))?;
+        entry.options.extend(options.clone());
+        Ok(entry)
+    })
+}
+
+/// The URL of a saved name. Secrets are resolved after the config lock is
+/// released, since a `cmd:` source can take a while.
+pub fn get_connection_url(name: &str) -> Result<String, String> {
+    with_config(|cfg| cfg.saved(name))?.to_connection_url()
+}
+
+/// The resolved jump host of a saved name and the database address to
+/// forward to, if it connects through SSH.
+pub fn get_ssh_tunnel(name: &str) -> Result<Option<(SshTunnel, String, u16)>, String> {
+    with_config(|cfg| cfg.saved(name))?.resolved_ssh()
 }
 
 
@@ -226,13 +1056,197 @@ pub fn list_saved_configs() -> Result<Vec<String>, String> {
     Ok(cfg.list_entry_names())
 }
 
+/// Reloads the project connections for the workspace roots the client
+/// reported (or, with none, from the working directory up).
+pub fn set_project_roots(roots: &[std::path::PathBuf]) -> Result<usize, String> {
+    let files = crate::project::project_files(roots);
+    with_config(|cfg| cfg.set_project(&files))
+}
+
+/// Project connections, with the file each comes from and its policy.
+pub fn list_project() -> Result<serde_json::Value, String> {
+    with_config(|cfg| {
+        Ok(cfg
+            .project
+            .iter()
+            .map(|p| {
+                let mut info = serde_json::json!({ "file": p.file.display().to_string(), "read_only": p.read_only });
+                if cfg.is_saved(&p.entry.name) {
+                    info["ignored"] = "a saved server has this name and is used instead".into();
+                }
+                (p.entry.name.clone(), info)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into())
+    })
+}
+
+pub fn is_read_only(name: &str) -> bool {
+    with_config(|cfg| Ok(cfg.is_read_only(name))).unwrap_or(false)
+}
+
+/// Discovered (unsaved) connection names, with the file or variables each
+/// came from.
+pub fn list_discovered() -> Result<std::collections::BTreeMap<String, String>, String> {
+    with_config(|cfg| Ok(cfg.discovered.clone()))
+}
+
+pub fn import_connections(names: &[String], overwrite: bool) -> Result<serde_json::Value, String> {
+    with_config(|cfg| cfg.import_connections(names, overwrite).map_err(|e| e.to_string()))
+}
+
 pub fn get_saved_config_url(name: &str) -> Result<String, String> {
     let arc = CONFIG_INSTANCE
         .get()
         .ok_or_else(|| "Config not initialized.".to_string())?;
     let cfg = arc.lock().map_err(|e| format!("Config lock poisoned: {e}"))?;
-    cfg.get_connection_url(name)
+    cfg.lookup(name)
+        .map(|e| e.redacted_url())
         .ok_or_else(|| format!("No saved config found with name '{name}'."))
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(dbtype: &str, ip: &str, port: u16, password: &str) -> Entry {
+        Entry::new(
+            "t".into(),
+            ip.into(),
+            port,
+            "app user".into(),
+            password.into(),
+            dbtype.into(),
+            "app_db".into(),
+        )
+    }
+
+    /// Builds the connection URL and parses it back, which must give the
+    /// same entry.
+    fn round_trip(e: &Entry) -> (String, Entry) {
+        let url = e.to_connection_url().unwrap();
+        let back = Entry::from_url(e.name.clone(), &url).unwrap();
+        assert_eq!(
+            serde_json::to_value(&back).unwrap(),
+            serde_json::to_value(e).unwrap(),
+            "via {url}"
+        );
+        (url, back)
+    }
+
+    #[test]
+    fn password_with_reserved_characters() {
+        for password in ["p@ss:w/rd#1%", "%41", "a b+c?d=e&f", "ünï"] {
+            round_trip(&entry("postgres", "db.example.com", 5432, password));
+            round_trip(&entry("mysql", "db.example.com", 3306, password));
+        }
+        let (url, _) = round_trip(&entry("postgres", "h", 5432, "p@ss:1"));
+        assert_eq!(url, "postgres://app%20user:p%40ss%3A1@h:5432/app_db");
+    }
+
+    #[test]
+    fn percent_encoding_round_trips() {
+        let all: String = (1u8..=127).map(char::from).chain("é€😀".chars()).collect();
+        assert_eq!(
+            percent_decode(&percent_encode(&all)).as_deref(),
+            Some(all.as_str())
+        );
+        // A stray '%' is kept, like libpq does; only invalid UTF-8 fails.
+        assert_eq!(percent_decode("100%").as_deref(), Some("100%"));
+        assert_eq!(percent_decode("%ff"), None);
+    }
+
+    #[test]
+    fn ipv6_host() {
+        let (url, back) = round_trip(&entry("postgres", "::1", 5433, "pw"));
+        assert_eq!(url, "postgres://app%20user:pw@[::1]:5433/app_db");
+        assert_eq!(back.host(), "::1");
+        round_trip(&entry("mysql", "fe80::1", 3306, "pw"));
+    }
+
+    #[test]
+    fn missing_port_is_the_default() {
+        let pg = Entry::from_url(String::new(), "postgres://u:p@db/app").unwrap();
+        assert_eq!(pg.port, 5432);
+        let my = Entry::from_url(String::new(), "mysql://u:p@db/app").unwrap();
+        assert_eq!(my.port, 3306);
+        let local = Entry::from_url(String::new(), "postgres:///app").unwrap();
+        assert_eq!(
+            (local.host(), local.port, local.database.as_str()),
+            ("localhost", 5432, "app")
+        );
+    }
+
+    #[test]
+    fn socket_parameters() {
+        let (url, _) = round_trip(&entry("postgres", "/var/run/postgresql", 5432, "pw"));
+        assert!(
+            url.contains("@localhost:5432/app_db?host=%2Fvar%2Frun%2Fpostgresql"),
+            "{url}"
+        );
+        let (url, _) = round_trip(&entry("mysql", "/var/run/mysqld/mysqld.sock", 3306, "pw"));
+        assert!(
+            url.contains("?socket=%2Fvar%2Frun%2Fmysqld%2Fmysqld.sock"),
+            "{url}"
+        );
+
+        let pg = Entry::from_url(String::new(), "postgres:///app?host=/tmp").unwrap();
+        assert_eq!(pg.host(), "/tmp");
+        let encoded =
+            Entry::from_url(String::new(), "postgres://u@%2Fvar%2Frun%2Fpostgresql/app").unwrap();
+        assert_eq!(encoded.host(), "/var/run/postgresql");
+        let my = Entry::from_url(
+            String::new(),
+            "mysql://u@localhost/app?socket=/tmp/mysql.sock",
+        )
+        .unwrap();
+        assert_eq!(my.host(), "/tmp/mysql.sock");
+        // A host parameter that is not a path is an ordinary option.
+        assert!(Entry::from_url(String::new(), "mysql://u@h/app?socket=rel")
+            .unwrap()
+            .options
+            .contains_key("socket"));
+    }
+
+    #[test]
+    fn options() {
+        let options = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let pg = entry("postgres", "h", 5432, "pw")
+            .with_options(options(&[
+                ("sslmode", "verify-full"),
+                ("sslrootcert", "/etc/ssl/ca b.pem"),
+                ("application_name", "db-mcp"),
+                ("connect_timeout", "5"),
+            ]))
+            .unwrap();
+        let (url, _) = round_trip(&pg);
+        assert!(url.contains("sslmode=verify-full"), "{url}");
+
+        let my = entry("mysql", "h", 3306, "pw")
+            .with_options(options(&[
+                ("sslmode", "verify-full"),
+                ("sslrootcert", "/ca.pem"),
+                ("charset", "utf8mb4"),
+            ]))
+            .unwrap();
+        let (url, _) = round_trip(&my);
+        assert!(
+            url.contains("ssl-mode=VERIFY_IDENTITY") && url.contains("ssl-ca=%2Fca.pem"),
+            "{url}"
+        );
+
+        assert!(entry("mysql", "h", 3306, "")
+            .with_options(options(&[("sslmode", "allow")]))
+            .is_err());
+        assert!(entry("postgres", "h", 5432, "")
+            .with_options(options(&[("charset", "utf8")]))
+            .is_err());
+    }
+}
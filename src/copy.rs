@@ -0,0 +1,338 @@
+use crate::db::{
+    decode_cell, describe_query, primary_key, qualified_table, quote_ident, quote_literal, readable_columns, to_hex,
+    Cell, DbKind, ReadableColumn, SharedState,
+};
+use anyhow::{anyhow, Result};
+use futures_util::TryStreamExt;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// What to do when a copied row collides with an existing key.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Conflict {
+    Fail,
+    Skip,
+    Overwrite,
+}
+
+impl Conflict {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "fail" => Ok(Self::Fail),
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            other => Err(anyhow!("Unknown on_conflict '{other}'. Use fail, skip or overwrite.")),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Fail => "fail",
+            Self::Skip => "skip",
+            Self::Overwrite => "overwrite",
+        }
+    }
+}
+
+pub enum CopySource<'a> {
+    Table { table: &'a str, filter: Option<&'a str> },
+    Query(&'a str),
+}
+
+pub struct CopyOptions {
+    /// Create the target table from the source columns if it does not exist.
+    pub create_target: bool,
+    pub batch_size: usize,
+    pub on_conflict: Conflict,
+    /// Key for `overwrite` and for a created table; defaults to the target's
+    /// (or source table's) primary key.
+    pub key_columns: Vec<String>,
+}
+
+/// Streams rows from a table or query on one connection into a table on
+/// another (or the same) connection, in multi-row `INSERT` batches. Each
+/// batch commits on its own, so a failure leaves earlier batches in place.
+pub async fn copy_rows(
+    source: &SharedState,
+    from: CopySource<'_>,
+    target: &SharedState,
+    target_table: &str,
+    opts: &CopyOptions,
+) -> Result<Value> {
+    // Source and target may be the same connection; take what is needed and
+    // release the locks before streaming.
+    let (src_pool, src_kind, src_url, src_timeout) = {
+        let st = source.lock().await;
+        (st.pool()?.clone(), st.kind()?, st.url.clone().unwrap_or_default(), st.pool_settings.acquire_timeout)
+    };
+    let (tgt_pool, tgt_kind) = {
+        let st = target.lock().await;
+        st.writable()?;
+        // The source stream keeps one connection while each batch is
+        // inserted through another, so one pool must have room for both.
+        if Arc::ptr_eq(source, target) && st.pool_settings.max_connections < 2 {
+            return Err(anyhow!(
+                "Copying within one connection needs two of its pool connections (one reads while the other writes), \
+                 but its max_connections is {}. Reconnect with options \"max_connections=2\" or more.",
+                st.pool_settings.max_connections
+            ));
+        }
+        (st.pool()?.clone(), st.kind()?)
+    };
+
+    let (columns, select, source_key) = match &from {
+        CopySource::Table { table, filter } => {
+            let mut columns = readable_columns(&src_pool, src_kind, table).await?;
+            for c in &mut columns {
+                // PostgreSQL prints offsets as `+00`, which MySQL rejects.
+                if src_kind == DbKind::Postgres && tgt_kind == DbKind::MySQL && c.base_type == "timestamptz" {
+                    let q = quote_ident(src_kind, &c.name);
+                    c.expr = format!("to_char({q} AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS.US') AS {q}");
+                }
+            }
+            let exprs: Vec<&str> = columns.iter().map(|c| c.expr.as_str()).collect();
+            let select = format!(
+                "SELECT {} FROM {}{}",
+                exprs.join(", "),
+                qualified_table(src_kind, table),
+                filter.map(|f| format!(" WHERE ({f})")).unwrap_or_default()
+            );
+            let key = primary_key(&src_pool, src_kind, table).await?;
+            (columns, select, key)
+        }
+        CopySource::Query(sql) => {
+            let sql = sql.trim().trim_end_matches(';');
+            let columns = describe_query(&src_url, src_timeout, src_kind, sql).await?;
+            let exprs: Vec<&str> = columns.iter().map(|c| c.expr.as_str()).collect();
+            let select = format!("SELECT {} FROM ({sql}) q", exprs.join(", "));
+            (columns, select, Vec::new())
+        }
+    };
+
+    let mut created = false;
+    let mut translated = false;
+    let target_columns = match readable_columns(&tgt_pool, tgt_kind, target_table).await {
+        Ok(cols) => cols,
+        Err(_) if opts.create_target => {
+            let key = if opts.key_columns.is_empty() { &source_key } else { &opts.key_columns };
+            let verbatim = src_kind == tgt_kind && matches!(from, CopySource::Table { .. });
+            let ddl = create_table_sql(tgt_kind, target_table, &columns, key, verbatim);
+            if let Err(e) = sqlx::query(&ddl).execute(&tgt_pool).await {
+                if !verbatim {
+                    return Err(anyhow!("Could not create '{target_table}': {e}\n{ddl}"));
+                }
+                // Typically an enum or domain missing on the target; fall
+                // back to portable types.
+                let ddl = create_table_sql(tgt_kind, target_table, &columns, key, false);
+                sqlx::query(&ddl)
+                    .execute(&tgt_pool)
+                    .await
+                    .map_err(|e| anyhow!("Could not create '{target_table}': {e}\n{ddl}"))?;
+                translated = true;
+            }
+            target.lock().await.schema_cache.invalidate();
+            created = true;
+            readable_columns(&tgt_pool, tgt_kind, target_table).await?
+        }
+        Err(e) => return Err(anyhow!("{e} Pass create_target to create it from the source columns.")),
+    };
+
+    // Only columns the target has are written; the rest are reported.
+    let mut indexes = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, c) in columns.iter().enumerate() {
+        if target_columns.iter().any(|t| t.name == c.name) {
+            indexes.push(i);
+        } else {
+            skipped.push(c.name.clone());
+        }
+    }
+    if indexes.is_empty() {
+        return Err(anyhow!("None of the source columns exist in '{target_table}'."));
+    }
+    let names: Vec<String> = indexes.iter().map(|&i| columns[i].name.clone()).collect();
+
+    let key = if !opts.key_columns.is_empty() {
+        opts.key_columns.clone()
+    } else {
+        primary_key(&tgt_pool, tgt_kind, target_table).await?
+    };
+    if opts.on_conflict == Conflict::Overwrite && key.is_empty() {
+        return Err(anyhow!(
+            "on_conflict 'overwrite' needs a key: '{target_table}' has no primary key, pass key_columns."
+        ));
+    }
+    let insert = InsertBuilder::new(tgt_kind, target_table, &names, &key, opts.on_conflict);
+
+    // Cleared before the first write, so a copy that fails part way does
+    // not leave column_values answering from the old rows.
+    target.lock().await.schema_cache.invalidate_values();
+    let (mut read, mut written, mut batches) = (0u64, 0u64, 0u64);
+    let mut batch: Vec<String> = Vec::with_capacity(opts.batch_size);
+    let mut stream = sqlx::query(&select).fetch(&src_pool);
+    loop {
+        let row = stream.try_next().await.map_err(|e| anyhow!("Reading source failed after {read} rows: {e}"))?;
+        if let Some(row) = &row {
+            let mut values = Vec::with_capacity(indexes.len());
+            for &i in &indexes {
+                values.push(literal(tgt_kind, &decode_cell(row, i, &columns[i])?));
+            }
+            batch.push(format!("({})", values.join(", ")));
+            read += 1;
+        }
+        if batch.len() >= opts.batch_size || (row.is_none() && !batch.is_empty()) {
+            let sql = insert.build(&batch);
+            let result = sqlx::query(&sql)
+                .execute(&tgt_pool)
+                .await
+                .map_err(|e| anyhow!("Insert failed after {written} rows were written: {e}"))?;
+            written += match opts.on_conflict {
+                // MySQL counts an updated row twice; report rows handled.
+                Conflict::Overwrite => batch.len() as u64,
+                _ => result.rows_affected(),
+            };
+            batches += 1;
+            batch.clear();
+        }
+        if row.is_none() {
+            break;
+        }
+    }
+
+    Ok(json!({
+        "target_table": target_table,
+        "created": created,
+        "types_translated": translated,
+        "columns": names,
+        "columns_skipped": skipped,
+        "on_conflict": opts.on_conflict.label(),
+        "rows_read": read,
+        "rows_written": written,
+        "rows_skipped": read.saturating_sub(written),
+        "batches": batches
+    }))
+}
+
+/// Renders a value for the target. Numbers are quoted so the server coerces
+/// them to whatever the target column is (e.g. MySQL `tinyint(1)` → boolean).
+fn literal(kind: DbKind, cell: &Cell) -> String {
+    match (cell, kind) {
+        (Cell::Null, _) => "NULL".into(),
+        (Cell::Bool(b), DbKind::Postgres) => if *b { "TRUE" } else { "FALSE" }.into(),
+        (Cell::Bool(b), DbKind::MySQL) => if *b { "1" } else { "0" }.into(),
+        (Cell::Int(n), _) => format!("'{n}'"),
+        (Cell::Float(f), _) => format!("'{f}'"),
+        (Cell::Text(s), _) => quote_literal(kind, s),
+        (Cell::Bytes(b), DbKind::Postgres) => format!("'\\x{}'::bytea", to_hex(b)),
+        (Cell::Bytes(b), DbKind::MySQL) => format!("X'{}'", to_hex(b)),
+    }
+}
+
+struct InsertBuilder {
+    head: String,
+    tail: String,
+}
+
+impl InsertBuilder {
+    fn new(kind: DbKind, table: &str, columns: &[String], key: &[String], conflict: Conflict) -> Self {
+        let quoted: Vec<String> = columns.iter().map(|c| quote_ident(kind, c)).collect();
+        let updates: Vec<String> = columns
+            .iter()
+            .filter(|c| !key.contains(c))
+            .map(|c| {
+                let q = quote_ident(kind, c);
+                match kind {
+                    DbKind::Postgres => format!("{q} = EXCLUDED.{q}"),
+                    DbKind::MySQL => format!("{q} = VALUES({q})"),
+                }
+            })
+            .collect();
+        let verb = if kind == DbKind::MySQL && conflict == Conflict::Skip { "INSERT IGNORE" } else { "INSERT" };
+        let head = format!("{verb} INTO {} ({}) VALUES ", qualified_table(kind, table), quoted.join(", "));
+        let tail = match (kind, conflict) {
+            (_, Conflict::Fail) | (DbKind::MySQL, Conflict::Skip) => String::new(),
+            (DbKind::Postgres, Conflict::Skip) => " ON CONFLICT DO NOTHING".into(),
+            (DbKind::Postgres, Conflict::Overwrite) => {
+                let keys: Vec<String> = key.iter().map(|k| quote_ident(kind, k)).collect();
+                if updates.is_empty() {
+                    format!(" ON CONFLICT ({}) DO NOTHING", keys.join(", "))
+                } else {
+                    format!(" ON CONFLICT ({}) DO UPDATE SET {}", keys.join(", "), updates.join(", "))
+                }
+            }
+            (DbKind::MySQL, Conflict::Overwrite) => {
+                // A no-op assignment keeps all-key tables valid SQL.
+                let first = quote_ident(kind, &columns[0]);
+                let set = if updates.is_empty() { format!("{first} = {first}") } else { updates.join(", ") };
+                format!(" ON DUPLICATE KEY UPDATE {set}")
+            }
+        };
+        Self { head, tail }
+    }
+
+    fn build(&self, rows: &[String]) -> String {
+        format!("{}{}{}", self.head, rows.join(", "), self.tail)
+    }
+}
+
+/// `verbatim` keeps the source's declared types (same dialect, table source);
+/// otherwise each type is translated for the target.
+fn create_table_sql(to: DbKind, table: &str, columns: &[ReadableColumn], key: &[String], verbatim: bool) -> String {
+    let mut lines: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            let ty = if verbatim { c.data_type.clone() } else { map_type(to, &c.base_type, &c.data_type) };
+            format!("    {} {ty}", quote_ident(to, &c.name))
+        })
+        .collect();
+    if !key.is_empty() {
+        let cols: Vec<String> = key.iter().map(|k| quote_ident(to, k)).collect();
+        lines.push(format!("    PRIMARY KEY ({})", cols.join(", ")));
+    }
+    format!("CREATE TABLE {} (\n{}\n)", qualified_table(to, table), lines.join(",\n"))
+}
+
+/// Translates a source column type into one the target dialect accepts.
+/// `declared` is the full declared type (e.g. `numeric(10,2)`), from which
+/// length / precision is carried over.
+fn map_type(to: DbKind, base: &str, declared: &str) -> String {
+    let declared = declared.to_lowercase();
+    let params = declared
+        .find('(')
+        .and_then(|start| declared[start..].find(')').map(|end| declared[start..start + end + 1].to_string()))
+        .unwrap_or_default();
+    let unsigned = declared.contains("unsigned");
+    let pg = to == DbKind::Postgres;
+    let base = base.trim_end_matches(" unsigned");
+    match base {
+        "bool" | "boolean" => if pg { "BOOLEAN" } else { "TINYINT(1)" }.to_string(),
+        "tinyint" if declared.starts_with("tinyint(1)") => if pg { "BOOLEAN" } else { "TINYINT(1)" }.into(),
+        "tinyint" => if pg { "SMALLINT" } else { "TINYINT" }.into(),
+        "int2" | "smallint" => "SMALLINT".into(),
+        "int4" | "int" | "integer" | "mediumint" | "year" => {
+            if unsigned { "BIGINT" } else if pg { "INTEGER" } else { "INT" }.into()
+        }
+        "int8" | "bigint" => if unsigned && pg { "NUMERIC(20)" } else { "BIGINT" }.into(),
+        "float4" | "real" | "float" => if pg { "REAL" } else { "FLOAT" }.into(),
+        "float8" | "double" | "double precision" => if pg { "DOUBLE PRECISION" } else { "DOUBLE" }.into(),
+        "numeric" | "decimal" => match (pg, params.is_empty()) {
+            (true, _) => format!("NUMERIC{params}"),
+            (false, true) => "DECIMAL(65,30)".into(),
+            (false, false) => format!("DECIMAL{params}"),
+        },
+        "varchar" | "character varying" | "char" | "bpchar" | "character" if !params.is_empty() => {
+            let fixed = matches!(base, "char" | "bpchar" | "character");
+            format!("{}{params}", if fixed { "CHAR" } else { "VARCHAR" })
+        }
+        "date" => "DATE".into(),
+        "timestamp" | "datetime" => if pg { "TIMESTAMP" } else { "DATETIME(6)" }.into(),
+        "timestamptz" => if pg { "TIMESTAMPTZ" } else { "DATETIME(6)" }.into(),
+        "time" | "timetz" => if pg { "TIME" } else { "TIME(6)" }.into(),
+        "json" | "jsonb" => if pg { "JSONB" } else { "JSON" }.into(),
+        "uuid" => if pg { "UUID" } else { "CHAR(36)" }.into(),
+        "bytea" | "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => {
+            if pg { "BYTEA" } else { "LONGBLOB" }.into()
+        }
+        _ => if pg { "TEXT" } else { "LONGTEXT" }.into(),
+    }
+}
@@ -0,0 +1,399 @@
+use crate::db::{
+    estimated_rows, primary_key, qualified_table, quote_ident, quote_literal, readable_columns, DbKind,
+    ReadableColumn, SharedState,
+};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use sqlx::{AnyPool, Row};
+use std::collections::{BTreeMap, HashMap};
+
+/// Separates key / row parts before hashing. A control character keeps
+/// `('a|b', 'c')` and `('a', 'b|c')` apart.
+const SEP: &str = "\u{1f}";
+/// Stands in for NULL so it hashes differently from an empty string.
+const NULL_MARK: &str = "\u{1e}";
+/// Differing chunks fetched per query while drilling down.
+const CHUNKS_PER_QUERY: usize = 16;
+/// Chunk numbers come from a 32-bit hash, so no finer split exists.
+const MAX_CHUNKS: u64 = 1 << 32;
+
+pub struct TableSide<'a> {
+    pub state: SharedState,
+    /// Connection name, echoed back in the result.
+    pub connection: &'a str,
+    pub table: &'a str,
+}
+
+pub struct DataDiffOptions<'a> {
+    /// Defaults to the source table's primary key.
+    pub key_columns: Vec<String>,
+    /// Defaults to every non-key column present on both sides.
+    pub columns: Vec<String>,
+    /// SQL condition applied to both sides, e.g. `created_at >= '2024-01-01'`.
+    pub filter: Option<&'a str>,
+    pub chunk_size: u64,
+    /// Rows listed per category; counts are always complete.
+    pub max_rows: usize,
+}
+
+/// One side of the comparison with everything needed to build its queries.
+struct Side {
+    kind: DbKind,
+    pool: AnyPool,
+    table: String,
+    columns: Vec<ReadableColumn>,
+    filter: Option<String>,
+}
+
+/// Compares a table's rows across two connections (or two tables) by key.
+///
+/// Rows are spread over chunks by a hash of their key, and each side reports
+/// only a row count and checksum per chunk. Keys and row hashes are then
+/// fetched for differing chunks only, and full values only for the rows that
+/// are actually listed, so neither table is ever held in memory. The chunk
+/// count starts from the planner's row estimate, which may be far off (or 0
+/// before the table is analyzed), so a differing chunk that turns out larger
+/// than `chunk_size` is split again before its keys are fetched.
+pub async fn diff_table_data(source: TableSide<'_>, target: TableSide<'_>, opts: &DataDiffOptions<'_>) -> Result<Value> {
+    // Both sides may share one connection, so never hold the lock while querying.
+    let (src_pool, src_kind) = {
+        let st = source.state.lock().await;
+        (st.pool()?.clone(), st.kind()?)
+    };
+    let (tgt_pool, tgt_kind) = {
+        let st = target.state.lock().await;
+        (st.pool()?.clone(), st.kind()?)
+    };
+    let src = Side {
+        kind: src_kind,
+        columns: readable_columns(&src_pool, src_kind, source.table).await?,
+        pool: src_pool,
+        table: source.table.to_string(),
+        filter: opts.filter.map(str::to_string),
+    };
+    let tgt = Side {
+        kind: tgt_kind,
+        columns: readable_columns(&tgt_pool, tgt_kind, target.table).await?,
+        pool: tgt_pool,
+        table: target.table.to_string(),
+        filter: opts.filter.map(str::to_string),
+    };
+
+    let keys = if opts.key_columns.is_empty() {
+        let pk = primary_key(&src.pool, src.kind, &src.table).await?;
+        if pk.is_empty() {
+            return Err(anyhow!(
+                "Table '{}' has no primary key; pass key_columns to say how rows are matched.",
+                src.table
+            ));
+        }
+        pk
+    } else {
+        opts.key_columns.clone()
+    };
+    for k in &keys {
+        for side in [&src, &tgt] {
+            if side.column(k).is_none() {
+                return Err(anyhow!("Key column '{k}' not found in '{}'.", side.table));
+            }
+        }
+    }
+
+    let shared: Vec<String> = src
+        .columns
+        .iter()
+        .filter(|c| !keys.contains(&c.name) && tgt.column(&c.name).is_some())
+        .map(|c| c.name.clone())
+        .collect();
+    let compared = if opts.columns.is_empty() {
+        shared
+    } else {
+        for c in &opts.columns {
+            if !shared.contains(c) {
+                return Err(anyhow!("Column '{c}' is not a non-key column of both tables."));
+            }
+        }
+        opts.columns.clone()
+    };
+    let only_in = |a: &Side, b: &Side| -> Vec<String> {
+        a.columns.iter().filter(|c| b.column(&c.name).is_none()).map(|c| c.name.clone()).collect()
+    };
+
+    let rows = src.row_estimate().await.max(tgt.row_estimate().await);
+    let chunks = rows.div_ceil(opts.chunk_size.max(1)).max(1);
+
+    let src_chunks = src.chunk_checksums(&keys, &compared, chunks, None).await?;
+    let tgt_chunks = tgt.chunk_checksums(&keys, &compared, chunks, None).await?;
+    let differing = differing_chunks(&src_chunks, &tgt_chunks);
+    let chunks_differing = differing.len();
+
+    // Split oversized differing chunks until each holds about `chunk_size`
+    // rows. Chunk numbers at a finer level are the hash modulo a multiple of
+    // the coarser count, so each lies within one coarser chunk.
+    let mut small: Vec<(u64, Vec<u64>)> = Vec::new();
+    let (mut level, mut differing, mut level_src, mut level_tgt) = (chunks, differing, src_chunks.clone(), tgt_chunks.clone());
+    loop {
+        let size = |b: &u64| {
+            let count = |m: &HashMap<u64, (i64, String)>| m.get(b).map_or(0, |(n, _)| *n).max(0) as u64;
+            count(&level_src).max(count(&level_tgt))
+        };
+        let largest = differing.iter().map(size).max().unwrap_or(0);
+        let factor = largest.div_ceil(opts.chunk_size.max(1)).min(MAX_CHUNKS / level);
+        let (big, fits): (Vec<u64>, Vec<u64>) = differing.iter().partition(|b| factor > 1 && size(b) > opts.chunk_size);
+        if !fits.is_empty() {
+            small.push((level, fits));
+        }
+        if big.is_empty() {
+            break;
+        }
+        let finer = level * factor;
+        level_src = src.chunk_checksums(&keys, &compared, finer, Some((level, &big))).await?;
+        level_tgt = tgt.chunk_checksums(&keys, &compared, finer, Some((level, &big))).await?;
+        differing = differing_chunks(&level_src, &level_tgt);
+        level = finer;
+    }
+
+    let (mut added, mut removed, mut changed) = (Vec::new(), Vec::new(), Vec::new());
+    let (mut added_count, mut removed_count, mut changed_count) = (0usize, 0usize, 0usize);
+    let batches = small.iter().flat_map(|(level, list)| list.chunks(CHUNKS_PER_QUERY).map(move |b| (*level, b)));
+    for (level, batch) in batches {
+        let src_rows = src.row_hashes(&keys, &compared, level, batch).await?;
+        let tgt_rows = tgt.row_hashes(&keys, &compared, level, batch).await?;
+        for (key, hash) in &src_rows {
+            match tgt_rows.get(key) {
+                None => {
+                    added_count += 1;
+                    if added.len() < opts.max_rows {
+                        added.push(key.clone());
+                    }
+                }
+                Some(other) if other != hash => {
+                    changed_count += 1;
+                    if changed.len() < opts.max_rows {
+                        changed.push(key.clone());
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        for key in tgt_rows.keys().filter(|k| !src_rows.contains_key(*k)) {
+            removed_count += 1;
+            if removed.len() < opts.max_rows {
+                removed.push(key.clone());
+            }
+        }
+    }
+
+    let src_values = src.row_values(&keys, &compared, &changed).await?;
+    let tgt_values = tgt.row_values(&keys, &compared, &changed).await?;
+    let changed_rows: Vec<Value> = changed
+        .iter()
+        .map(|key| {
+            let empty = Vec::new();
+            let a = src_values.get(key).unwrap_or(&empty);
+            let b = tgt_values.get(key).unwrap_or(&empty);
+            let mut cols = Map::new();
+            for (i, name) in compared.iter().enumerate() {
+                let (x, y) = (a.get(i).cloned().flatten(), b.get(i).cloned().flatten());
+                if x != y {
+                    cols.insert(name.clone(), json!({ "source": x, "target": y }));
+                }
+            }
+            json!({ "key": key_object(&keys, key), "columns": cols })
+        })
+        .collect();
+
+    let total = |m: &HashMap<u64, (i64, String)>| m.values().map(|(n, _)| n).sum::<i64>();
+    Ok(json!({
+        "source": { "connection": source.connection, "table": source.table, "rows": total(&src_chunks) },
+        "target": { "connection": target.connection, "table": target.table, "rows": total(&tgt_chunks) },
+        "key_columns": keys,
+        "columns_compared": compared,
+        "columns_only_in_source": only_in(&src, &tgt),
+        "columns_only_in_target": only_in(&tgt, &src),
+        "chunks": chunks,
+        "chunks_differing": chunks_differing,
+        "identical": added_count + removed_count + changed_count == 0,
+        "added_count": added_count,
+        "removed_count": removed_count,
+        "changed_count": changed_count,
+        "added": added.iter().map(|k| key_object(&keys, k)).collect::<Vec<_>>(),
+        "removed": removed.iter().map(|k| key_object(&keys, k)).collect::<Vec<_>>(),
+        "changed": changed_rows,
+        "truncated": added_count.max(removed_count).max(changed_count) > opts.max_rows
+    }))
+}
+
+/// Chunks whose count or checksum differs between the sides, in order.
+fn differing_chunks(a: &HashMap<u64, (i64, String)>, b: &HashMap<u64, (i64, String)>) -> Vec<u64> {
+    let mut differing: Vec<u64> = a.keys().chain(b.keys()).filter(|c| a.get(c) != b.get(c)).copied().collect();
+    differing.sort_unstable();
+    differing.dedup();
+    differing
+}
+
+fn key_object(keys: &[String], key: &str) -> Value {
+    let mut obj = Map::new();
+    for (name, part) in keys.iter().zip(key.split(SEP)) {
+        obj.insert(name.clone(), Value::String(part.to_string()));
+    }
+    Value::Object(obj)
+}
+
+impl Side {
+    fn column(&self, name: &str) -> Option<&ReadableColumn> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    async fn row_estimate(&self) -> u64 {
+        match estimated_rows(&self.pool, self.kind, &self.table).await {
+            Ok(Some(n)) if n > 0 => n as u64,
+            _ => 0,
+        }
+    }
+
+    /// The column's value as text, normalised so both dialects agree on
+    /// booleans and binary data.
+    fn text_expr(&self, name: &str) -> String {
+        let q = quote_ident(self.kind, name);
+        let col = self.column(name);
+        let base = col.map(|c| c.base_type.as_str()).unwrap_or("");
+        let binary = col.is_some_and(|c| c.binary);
+        match self.kind {
+            DbKind::Postgres if base == "bool" => format!("{q}::int::text"),
+            DbKind::Postgres if binary => format!("encode({q}, 'hex')"),
+            DbKind::Postgres => format!("{q}::text"),
+            DbKind::MySQL if binary => format!("LOWER(HEX({q}))"),
+            DbKind::MySQL => format!("CAST({q} AS CHAR)"),
+        }
+    }
+
+    /// Concatenates the columns' text, NULL-safe, separated by `SEP`.
+    fn joined(&self, names: &[String]) -> String {
+        let null = quote_literal(self.kind, NULL_MARK);
+        let parts: Vec<String> = names
+            .iter()
+            .map(|n| format!("COALESCE({}, {null})", self.text_expr(n)))
+            .collect();
+        if parts.is_empty() {
+            return "''".into();
+        }
+        let sep = quote_literal(self.kind, SEP);
+        match self.kind {
+            DbKind::Postgres => parts.join(&format!(" || {sep} || ")),
+            DbKind::MySQL => format!("CONCAT({})", parts.join(&format!(", {sep}, "))),
+        }
+    }
+
+    /// First 32 bits of the text's MD5 as an unsigned integer.
+    fn hash32(&self, text: &str) -> String {
+        match self.kind {
+            DbKind::Postgres => format!("('x' || substr(md5({text}), 1, 8))::bit(32)::bigint"),
+            DbKind::MySQL => format!("CAST(CONV(SUBSTR(MD5({text}), 1, 8), 16, 10) AS UNSIGNED)"),
+        }
+    }
+
+    fn to_text(&self, expr: &str) -> String {
+        match self.kind {
+            DbKind::Postgres => format!("({expr})::text"),
+            DbKind::MySQL => format!("CAST({expr} AS CHAR)"),
+        }
+    }
+
+    fn chunk_expr(&self, keys: &[String], chunks: u64) -> String {
+        format!("{} % {chunks}", self.hash32(&self.joined(keys)))
+    }
+
+    fn where_clause(&self, condition: String) -> String {
+        match &self.filter {
+            Some(f) => format!("WHERE ({f}) AND {condition}"),
+            None => format!("WHERE {condition}"),
+        }
+    }
+
+    /// Row count and checksum per chunk, optionally only for rows in the
+    /// given chunks of a coarser split.
+    async fn chunk_checksums(
+        &self,
+        keys: &[String],
+        columns: &[String],
+        chunks: u64,
+        within: Option<(u64, &[u64])>,
+    ) -> Result<HashMap<u64, (i64, String)>> {
+        let filter = match within {
+            Some((coarse, list)) => {
+                let list: Vec<String> = list.iter().map(|b| b.to_string()).collect();
+                self.where_clause(format!("{} IN ({})", self.chunk_expr(keys, coarse), list.join(", ")))
+            }
+            None => self.filter.as_deref().map(|f| format!("WHERE ({f})")).unwrap_or_default(),
+        };
+        let sql = format!(
+            "SELECT {chunk} AS chunk, COUNT(*) AS row_count, {checksum} AS checksum \
+             FROM (SELECT {expr} AS b, {hash} AS h FROM {table} {filter}) s GROUP BY b",
+            chunk = self.to_text("b"),
+            checksum = self.to_text("SUM(h)"),
+            expr = self.chunk_expr(keys, chunks),
+            hash = self.hash32(&self.joined(columns)),
+            table = qualified_table(self.kind, &self.table),
+        );
+        let rows = sqlx::query(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| anyhow!("checksum query on '{}' failed: {e}", self.table))?;
+        let mut out = HashMap::with_capacity(rows.len());
+        for r in &rows {
+            let chunk: String = r.try_get("chunk")?;
+            out.insert(
+                chunk.parse()?,
+                (r.try_get("row_count")?, r.try_get::<Option<String>, _>("checksum")?.unwrap_or_default()),
+            );
+        }
+        Ok(out)
+    }
+
+    /// Key → row hash for every row in the given chunks.
+    async fn row_hashes(&self, keys: &[String], columns: &[String], chunks: u64, batch: &[u64]) -> Result<BTreeMap<String, String>> {
+        let list: Vec<String> = batch.iter().map(|b| b.to_string()).collect();
+        let sql = format!(
+            "SELECT {key} AS row_key, MD5({row}) AS row_hash FROM {table} {filter}",
+            key = self.joined(keys),
+            row = self.joined(columns),
+            table = qualified_table(self.kind, &self.table),
+            filter = self.where_clause(format!("{} IN ({})", self.chunk_expr(keys, chunks), list.join(", "))),
+        );
+        let rows = sqlx::query(&sql).fetch_all(&self.pool).await?;
+        rows.iter()
+            .map(|r| Ok((r.try_get("row_key")?, r.try_get("row_hash")?)))
+            .collect()
+    }
+
+    /// Compared columns' text values for the given keys.
+    async fn row_values(&self, keys: &[String], columns: &[String], wanted: &[String]) -> Result<BTreeMap<String, Vec<Option<String>>>> {
+        if wanted.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let key = self.joined(keys);
+        let exprs: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{} AS c{i}", self.text_expr(c)))
+            .collect();
+        let list: Vec<String> = wanted.iter().map(|k| quote_literal(self.kind, k)).collect();
+        let sql = format!(
+            "SELECT {key} AS row_key{sep}{exprs} FROM {table} {filter}",
+            sep = if exprs.is_empty() { "" } else { ", " },
+            exprs = exprs.join(", "),
+            table = qualified_table(self.kind, &self.table),
+            filter = self.where_clause(format!("{key} IN ({})", list.join(", "))),
+        );
+        let mut out = BTreeMap::new();
+        for r in sqlx::query(&sql).fetch_all(&self.pool).await? {
+            let mut values = Vec::with_capacity(columns.len());
+            for i in 0..columns.len() {
+                values.push(r.try_get::<Option<String>, _>(i + 1)?);
+            }
+            out.insert(r.try_get("row_key")?, values);
+        }
+        Ok(out)
+    }
+}
@@ -1,7 +1,10 @@
 use anyhow::{anyhow, Result};
 use serde_json::{json, Value};
-use sqlx::{any::AnyPoolOptions, AnyPool, Column, Row, TypeInfo};
-use std::sync::Arc;
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::{AnyPool, Column, Row, TypeInfo};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tracing::info;
 
@@ -74,15 +77,116 @@ impl ConfigVsDBstate {
 
 pub type ConfigSharedState = Arc<Mutex<ConfigVsDBstate>>;
 
+/// Introspected schema for one connection, so repeated `describe_table` /
+/// `get_full_schema` calls don't go back to the database. Entries expire
+/// after `schema_cache_ttl()` and are dropped whenever DDL runs; distinct
+/// values are dropped after any write.
+#[derive(Default)]
+pub struct SchemaCache {
+    full: Option<(Instant, Value)>,
+    tables: HashMap<String, (Instant, Value)>,
+    /// Distinct values of low-cardinality columns, keyed by `column_values`'
+    /// arguments.
+    values: HashMap<String, (Instant, Value)>,
+}
+
+impl SchemaCache {
+    fn fresh(at: &Instant) -> bool {
+        at.elapsed() < schema_cache_ttl()
+    }
+
+    pub fn full(&self) -> Option<&Value> {
+        self.full.as_ref().filter(|(at, _)| Self::fresh(at)).map(|(_, v)| v)
+    }
+
+    pub fn table(&self, table: &str) -> Option<Value> {
+        if let Some((_, v)) = self.tables.get(table).filter(|(at, _)| Self::fresh(at)) {
+            return Some(v.clone());
+        }
+        let columns = self.full()?.get("schema")?.get(table)?;
+        Some(json!({ "table": table, "columns": columns }))
+    }
+
+    fn put_full(&mut self, schema: Value) {
+        self.full = Some((Instant::now(), schema));
+    }
+
+    fn put_table(&mut self, table: &str, described: Value) {
+        self.tables.insert(table.to_string(), (Instant::now(), described));
+    }
+
+    pub fn values(&self, key: &str) -> Option<Value> {
+        self.values
+            .get(key)
+            .filter(|(at, _)| Self::fresh(at))
+            .map(|(_, v)| v.clone())
+    }
+
+    pub fn put_values(&mut self, key: String, values: Value) {
+        self.values.insert(key, (Instant::now(), values));
+    }
+
+    /// Drops the cached distinct values, which any write can change.
+    pub fn invalidate_values(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn invalidate(&mut self) {
+        self.full = None;
+        self.tables.clear();
+        self.values.clear();
+    }
+}
+
+/// How long introspected schema stays cached. Set `DB_MCP_SCHEMA_CACHE_TTL`
+/// (seconds) to override; `0` disables caching.
+fn schema_cache_ttl() -> Duration {
+    static TTL: OnceLock<Duration> = OnceLock::new();
+    *TTL.get_or_init(|| {
+        let secs = std::env::var("DB_MCP_SCHEMA_CACHE_TTL")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(300);
+        Duration::from_secs(secs)
+    })
+}
+
 pub struct DbState {
     pub pool: Option<AnyPool>,
     pub kind: Option<DbKind>,
+    /// The URL as given, for display only.
+    pub display_url: Option<String>,
+    /// The URL without the pool options, for native driver connections.
     pub url: Option<String>,
+    pub schema_cache: SchemaCache,
+    /// Set by a project `read_only` policy (or `connect_database`); only
+    /// read statements are allowed.
+    pub read_only: bool,
+    /// SSH forward the connection goes through; closed with the connection.
+    pub tunnel: Option<crate::tunnel::Tunnel>,
+    pub pool_settings: PoolSettings,
 }
 
 impl DbState {
     pub fn new() -> Self {
-        Self { pool: None, kind: None, url: None }
+        Self {
+            pool: None,
+            kind: None,
+            display_url: None,
+            url: None,
+            schema_cache: SchemaCache::default(),
+            read_only: false,
+            tunnel: None,
+            pool_settings: PoolSettings::default(),
+        }
+    }
+
+    /// Fails for a read-only connection; called before any tool writes.
+    pub fn writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(anyhow!("This connection is read-only; only SELECT, SHOW, DESCRIBE, EXPLAIN and WITH queries are allowed."));
+        }
+        Ok(())
     }
 
     pub fn connected(&self) -> bool {
@@ -102,25 +206,215 @@ impl DbState {
 
 pub type SharedState = Arc<Mutex<DbState>>;
 
-pub async fn connect(state: &SharedState, url: &str) -> Result<String> {
+/// Connection options that configure the pool rather than the driver.
+const POOL_OPTIONS: [&str; 7] = [
+    "connect_timeout",
+    "acquire_timeout",
+    "max_connections",
+    "min_connections",
+    "idle_timeout",
+    "max_lifetime",
+    "test_before_acquire",
+];
+
+/// Pool settings of a connection. The defaults suit one client making a
+/// tool call at a time: a few connections, and a short wait for one so a
+/// stuck call fails instead of hanging the session.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a call waits for a free connection (or for a new one to open).
+    pub acquire_timeout: Duration,
+    /// Idle connections are closed after this long; `None` keeps them.
+    pub idle_timeout: Option<Duration>,
+    /// Connections are replaced after this long; `None` keeps them.
+    pub max_lifetime: Option<Duration>,
+    /// Ping a connection before handing it out, so one the server dropped
+    /// is replaced instead of failing the call.
+    pub test_before_acquire: bool,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(10),
+            idle_timeout: Some(Duration::from_secs(300)),
+            max_lifetime: Some(Duration::from_secs(1800)),
+            test_before_acquire: true,
+        }
+    }
+}
+
+impl PoolSettings {
+    fn options(&self) -> AnyPoolOptions {
+        AnyPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+            .max_lifetime(self.max_lifetime)
+            .test_before_acquire(self.test_before_acquire)
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "max_connections": self.max_connections,
+            "min_connections": self.min_connections,
+            "acquire_timeout_secs": self.acquire_timeout.as_secs(),
+            "idle_timeout_secs": self.idle_timeout.map(|d| d.as_secs()),
+            "max_lifetime_secs": self.max_lifetime.map(|d| d.as_secs()),
+            "test_before_acquire": self.test_before_acquire,
+        })
+    }
+}
+
+/// Replaces sqlx's bare "pool timed out" in a tool error with what it means
+/// for this connection and what to change.
+pub fn explain_pool_timeout(text: &str, name: &str, settings: &PoolSettings) -> Option<String> {
+    let timed_out = sqlx::Error::PoolTimedOut.to_string();
+    if !text.contains(&timed_out) {
+        return None;
+    }
+    let explained = format!(
+        "no connection of '{name}' was free within {}s (acquire_timeout): all {} of its pool's connections \
+         (max_connections) were busy, for example with another call or with this call waiting for a second \
+         one, or the server did not answer. Retry once the running work has finished, or raise \
+         max_connections / acquire_timeout in the connection's options",
+        settings.acquire_timeout.as_secs(),
+        settings.max_connections,
+    );
+    Some(text.replace(&timed_out, &explained))
+}
+
+/// Opens a pool for `url`. With a `tunnel`, `url` already points at its
+/// local end, and the tunnel lives as long as the connection.
+pub async fn connect(
+    state: &SharedState,
+    url: &str,
+    read_only: bool,
+    tunnel: Option<crate::tunnel::Tunnel>,
+) -> Result<String> {
     let kind = DbKind::from_url(url)?;
+    let (driver_url, settings) = split_pool_options(url)?;
 
-    let pool = AnyPoolOptions::new()
-        .max_connections(5)
-        .connect(url)
+    let mut options = settings.options();
+    if read_only {
+        // Enforced by the server too, so writes hidden in a CTE or function
+        // call fail as well. This is only the session default, which a query
+        // can turn off (`set_config` on PostgreSQL), so it is set again each
+        // time a connection is handed out; `execute_query` also runs reads in
+        // a read-only transaction.
+        let stmt = match kind {
+            DbKind::Postgres => "SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY",
+            DbKind::MySQL => "SET SESSION TRANSACTION READ ONLY",
+        };
+        options = options
+            .after_connect(move |conn, _| {
+                Box::pin(async move {
+                    sqlx::Executor::execute(conn, stmt).await?;
+                    Ok(())
+                })
+            })
+            .before_acquire(move |conn, _| {
+                Box::pin(async move {
+                    sqlx::Executor::execute(conn, stmt).await?;
+                    Ok(true)
+                })
+            });
+    }
+    let pool = options
+        .connect(&driver_url)
         .await
-        .map_err(|e| anyhow!("Connection failed: {e}"))?;
+        .map_err(|e| match e {
+            sqlx::Error::PoolTimedOut => {
+                anyhow!("Connection failed: timed out after {}s", settings.acquire_timeout.as_secs())
+            }
+            e => anyhow!("Connection failed: {e}"),
+        })?;
 
     let mut st = state.lock().await;
     if let Some(old) = st.pool.take() {
         old.close().await;
     }
+    if let Some(old) = st.tunnel.take() {
+        old.close().await;
+    }
+    let via = tunnel.as_ref().map(|t| format!(" via SSH {}", t.label)).unwrap_or_default();
+    st.tunnel = tunnel;
     st.pool = Some(pool);
     st.kind = Some(kind);
-    st.url = Some(url.to_string());
+    st.display_url = Some(url.to_string());
+    st.url = Some(driver_url);
+    st.read_only = read_only;
+    st.pool_settings = settings;
+    st.schema_cache.invalidate();
 
-    info!("Connected to {} at {url}", kind.label());
-    Ok(format!("Connected to {} ({})", kind.label(), redact_url(url)))
+    info!("Connected to {} at {}{via}", kind.label(), redact_url(url));
+    Ok(format!(
+        "Connected to {} ({}){via}{}",
+        kind.label(),
+        redact_url(url),
+        if read_only { " read-only" } else { "" }
+    ))
+}
+
+/// Takes the pool options out of a URL, since the drivers don't read them.
+/// `connect_timeout` (seconds) becomes the acquire timeout unless
+/// `acquire_timeout` is given too: acquiring is what opens a connection.
+fn split_pool_options(url: &str) -> Result<(String, PoolSettings)> {
+    let mut settings = PoolSettings::default();
+    if !POOL_OPTIONS.iter().any(|k| url.contains(&format!("{k}="))) {
+        return Ok((url.to_string(), settings));
+    }
+    let mut parsed = url::Url::parse(url).map_err(|e| anyhow!("Invalid connection string: {e}"))?;
+    let number = |key: &str, value: &str, min: u64| {
+        value.parse::<u64>().ok().filter(|n| *n >= min).ok_or_else(|| {
+            if min > 0 {
+                anyhow!("{key} must be a positive number")
+            } else {
+                anyhow!("{key} must be a number (0 turns it off)")
+            }
+        })
+    };
+    let seconds = |n: u64| (n > 0).then(|| Duration::from_secs(n));
+    let count = |key: &str, value: &str, min: u64| {
+        number(key, value, min).and_then(|n| u32::try_from(n).map_err(|_| anyhow!("{key} is too large")))
+    };
+    let (mut connect_timeout, mut acquire_timeout) = (None, None);
+    let mut rest = Vec::new();
+    for (k, v) in parsed.query_pairs() {
+        match k.as_ref() {
+            "connect_timeout" => connect_timeout = seconds(number(&k, &v, 1)?),
+            "acquire_timeout" => acquire_timeout = seconds(number(&k, &v, 1)?),
+            "idle_timeout" => settings.idle_timeout = seconds(number(&k, &v, 0)?),
+            "max_lifetime" => settings.max_lifetime = seconds(number(&k, &v, 0)?),
+            "max_connections" => settings.max_connections = count(&k, &v, 1)?,
+            "min_connections" => settings.min_connections = count(&k, &v, 0)?,
+            "test_before_acquire" => {
+                settings.test_before_acquire = v.parse().map_err(|_| anyhow!("test_before_acquire must be true or false"))?
+            }
+            _ => rest.push((k.into_owned(), v.into_owned())),
+        }
+    }
+    if settings.min_connections > settings.max_connections {
+        return Err(anyhow!(
+            "min_connections ({}) cannot exceed max_connections ({})",
+            settings.min_connections,
+            settings.max_connections
+        ));
+    }
+    if let Some(timeout) = acquire_timeout.or(connect_timeout) {
+        settings.acquire_timeout = timeout;
+    }
+    if rest.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(&rest);
+    }
+    Ok((parsed.to_string(), settings))
 }
 
 pub async fn disconnect(state: &SharedState) -> Result<String> {
@@ -128,7 +422,12 @@ pub async fn disconnect(state: &SharedState) -> Result<String> {
     if let Some(pool) = st.pool.take() {
         pool.close().await;
         st.kind = None;
+        st.display_url = None;
         st.url = None;
+        st.schema_cache.invalidate();
+        if let Some(tunnel) = st.tunnel.take() {
+            tunnel.close().await;
+        }
         Ok("Disconnected from database.".into())
     } else {
         Ok("No active connection.".into())
@@ -136,21 +435,23 @@ pub async fn disconnect(state: &SharedState) -> Result<String> {
 }
 
 pub async fn execute_query(state: &SharedState, sql: &str) -> Result<Value> {
-    let st = state.lock().await;
-    let pool = st.pool()?;
+    let mut st = state.lock().await;
+    let pool = st.pool()?.clone();
+    let pool = &pool;
 
-    let trimmed = sql.trim().to_uppercase();
-    let is_select = trimmed.starts_with("SELECT")
-        || trimmed.starts_with("SHOW")
-        || trimmed.starts_with("DESCRIBE")
-        || trimmed.starts_with("EXPLAIN")
-        || trimmed.starts_with("WITH");
+    let keyword = leading_keyword(sql);
+    let is_select = ["SELECT", "SHOW", "DESCRIBE", "EXPLAIN", "WITH"].contains(&keyword.as_str());
+    if !is_select {
+        st.writable()?;
+    }
 
     if is_select {
-        let rows = sqlx::query(sql)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| anyhow!("Query error: {e}"))?;
+        let rows = if st.read_only {
+            fetch_read_only(pool, st.kind()?, sql).await
+        } else {
+            sqlx::query(sql).fetch_all(pool).await
+        }
+        .map_err(|e| anyhow!("Query error: {e}"))?;
 
         let result: Vec<Value> = rows.iter().map(row_to_json).collect();
         Ok(json!({
@@ -163,6 +464,12 @@ pub async fn execute_query(state: &SharedState, sql: &str) -> Result<Value> {
             .await
             .map_err(|e| anyhow!("Query error: {e}"))?;
 
+        if !is_dml(&keyword) {
+            st.schema_cache.invalidate();
+        } else {
+            st.schema_cache.invalidate_values();
+        }
+
         Ok(json!({
             "rows_affected": res.rows_affected(),
             "message": format!("Query executed successfully. {} row(s) affected.", res.rows_affected())
@@ -170,6 +477,23 @@ pub async fn execute_query(state: &SharedState, sql: &str) -> Result<Value> {
     }
 }
 
+/// Runs a read in its own `READ ONLY` transaction, which nothing inside it
+/// can switch back to read-write, then rolls it back.
+async fn fetch_read_only(pool: &AnyPool, kind: DbKind, sql: &str) -> Result<Vec<AnyRow>, sqlx::Error> {
+    let begin = match kind {
+        DbKind::Postgres => "BEGIN READ ONLY",
+        DbKind::MySQL => "START TRANSACTION READ ONLY",
+    };
+    let mut conn = pool.acquire().await?;
+    sqlx::Executor::execute(&mut *conn, begin).await?;
+    let rows = sqlx::query(sql).fetch_all(&mut *conn).await;
+    if let Err(e) = sqlx::Executor::execute(&mut *conn, "ROLLBACK").await {
+        // Never hand a connection still inside the transaction back to the pool.
+        drop(conn.detach());
+        return Err(e);
+    }
+    rows
+}
 
 pub async fn list_databases(state: &SharedState) -> Result<Value> {
     let st = state.lock().await;
@@ -180,7 +504,7 @@ pub async fn list_databases(state: &SharedState) -> Result<Value> {
         DbKind::MySQL =>
             "SELECT schema_name AS `database` FROM information_schema.schemata ORDER BY schema_name",
         DbKind::Postgres =>
-            "SELECT datname AS database FROM pg_database WHERE datistemplate = false ORDER BY datname",
+            "SELECT datname::text AS database FROM pg_database WHERE datistemplate = false ORDER BY datname",
     };
 
     let rows = sqlx::query(sql).fetch_all(pool).await?;
@@ -204,7 +528,7 @@ pub async fn list_tables(state: &SharedState) -> Result<Value> {
              ORDER BY table_name"
         }
         DbKind::Postgres => {
-            "SELECT table_name FROM information_schema.tables \
+            "SELECT table_name::text FROM information_schema.tables \
              WHERE table_schema NOT IN ('pg_catalog','information_schema') \
              AND table_type = 'BASE TABLE' \
              ORDER BY table_name"
@@ -221,60 +545,574 @@ pub async fn list_tables(state: &SharedState) -> Result<Value> {
 }
 
 pub async fn describe_table(state: &SharedState, table: &str) -> Result<Value> {
+    let mut st = state.lock().await;
+    if let Some(cached) = st.schema_cache.table(table) {
+        return Ok(cached);
+    }
+    let pool = st.pool()?;
+    let kind = st.kind()?;
+    // `schema.table` as `get_full_schema` reports it; a bare name is the
+    // table the database would pick for it.
+    let filter = match (kind, split_table(table)) {
+        (DbKind::MySQL, (schema, name)) => format!(
+            "c.table_schema = {} AND c.table_name = {}",
+            schema.map_or("DATABASE()".into(), |s| quote_literal(kind, s)),
+            quote_literal(kind, name)
+        ),
+        (DbKind::Postgres, _) => format!(
+            "(c.table_schema::text, c.table_name::text) IN \
+             (SELECT n.nspname::text, c.relname::text FROM pg_catalog.pg_class c \
+              JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace WHERE {})",
+            pg_relation_filter(table)
+        ),
+    };
+
+    let rows = sqlx::query(&columns_sql(kind, &filter)).fetch_all(pool).await
+        .map_err(|e| anyhow!("describe_table error: {e}"))?;
+
+    if rows.is_empty() {
+        return Err(anyhow!("Table '{table}' not found or has no columns."));
+    }
+
+    let columns: Vec<Value> = rows
+        .iter()
+        .map(|r| {
+            let mut col = annotate_column(kind, row_to_json(r));
+            if let Some(obj) = col.as_object_mut() {
+                obj.remove("table_key");
+            }
+            col
+        })
+        .collect();
+    let described = json!({ "table": table, "columns": columns });
+    st.schema_cache.put_table(table, described.clone());
+    Ok(described)
+}
+
+/// Column metadata for every column matching `filter` (written against the
+/// `information_schema.columns c` / `information_schema.tables t` aliases).
+/// Rows carry a `table_key` — the bare table name, schema-qualified on
+/// PostgreSQL when outside `current_schema()` — and come back grouped by it.
+fn columns_sql(kind: DbKind, filter: &str) -> String {
+    match kind {
+        DbKind::MySQL => format!(
+            "SELECT CAST(c.table_name AS CHAR) AS table_key, \
+             CAST(c.column_name AS CHAR) AS column_name, \
+             CAST(c.data_type AS CHAR) AS data_type, \
+             CAST(c.is_nullable AS CHAR) AS is_nullable, \
+             CAST(c.column_default AS CHAR) AS column_default, \
+             CAST(c.character_maximum_length AS SIGNED) AS character_maximum_length, \
+             CAST(c.column_key AS CHAR) AS column_key, \
+             CAST(c.extra AS CHAR) AS extra, \
+             CAST(c.column_type AS CHAR) AS column_type \
+             FROM information_schema.columns c \
+             JOIN information_schema.tables t \
+               ON t.table_schema = c.table_schema AND t.table_name = c.table_name \
+             WHERE {filter} \
+             ORDER BY c.table_name, c.ordinal_position"
+        ),
+        DbKind::Postgres => format!(
+            "SELECT (CASE WHEN c.table_schema = current_schema() THEN c.table_name \
+                     ELSE c.table_schema || '.' || c.table_name END)::text AS table_key, \
+             c.column_name::text AS column_name, \
+             (CASE WHEN c.data_type = 'USER-DEFINED' THEN c.udt_name ELSE c.data_type END)::text AS data_type, \
+             c.is_nullable::text AS is_nullable, \
+             c.column_default::text AS column_default, \
+             c.character_maximum_length::bigint AS character_maximum_length, \
+             c.domain_name::text AS domain, \
+             (SELECT json_agg(e.enumlabel ORDER BY e.enumsortorder)::text \
+                FROM pg_catalog.pg_enum e \
+                JOIN pg_catalog.pg_type et ON et.oid = e.enumtypid \
+                JOIN pg_catalog.pg_namespace en ON en.oid = et.typnamespace \
+               WHERE et.typname = c.udt_name AND en.nspname = c.udt_schema) AS enum_values \
+             FROM information_schema.columns c \
+             JOIN information_schema.tables t \
+               ON t.table_schema = c.table_schema AND t.table_name = c.table_name \
+             WHERE {filter} \
+             ORDER BY 1, c.ordinal_position"
+        ),
+    }
+}
+
+pub async fn list_sequences(state: &SharedState) -> Result<Value> {
     let st = state.lock().await;
     let pool = st.pool()?;
     let kind = st.kind()?;
 
+    let sql = match kind {
+        DbKind::MySQL => {
+            "SELECT table_name, CAST(auto_increment AS SIGNED) AS next_value \
+             FROM information_schema.tables \
+             WHERE table_schema = DATABASE() AND auto_increment IS NOT NULL \
+             ORDER BY table_name"
+        }
+        DbKind::Postgres => {
+            "SELECT s.schemaname::text AS schema, s.sequencename::text AS sequence_name, \
+             s.data_type::text AS data_type, s.start_value, s.increment_by, \
+             s.min_value, s.max_value, s.last_value, s.cycle, \
+             (SELECT (d.refobjid::regclass)::text || '.' || a.attname::text \
+                FROM pg_catalog.pg_depend d \
+                JOIN pg_catalog.pg_attribute a \
+                  ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid \
+               WHERE d.classid = 'pg_catalog.pg_class'::regclass \
+                 AND d.objid = (quote_ident(s.schemaname) || '.' || quote_ident(s.sequencename))::regclass \
+                 AND d.deptype IN ('a', 'i') \
+               LIMIT 1) AS owned_by \
+             FROM pg_catalog.pg_sequences s \
+             WHERE s.schemaname NOT IN ('pg_catalog','information_schema') \
+             ORDER BY s.schemaname, s.sequencename"
+        }
+    };
+
+    let rows = sqlx::query(sql).fetch_all(pool).await
+        .map_err(|e| anyhow!("list_sequences error: {e}"))?;
+    let sequences: Vec<Value> = rows.iter().map(row_to_json).collect();
+
+    match kind {
+        DbKind::MySQL => Ok(json!({
+            "auto_increment": sequences,
+            "note": "MySQL has no standalone sequences; AUTO_INCREMENT counters are listed instead."
+        })),
+        DbKind::Postgres => Ok(json!({ "sequences": sequences })),
+    }
+}
+
+pub async fn table_stats(state: &SharedState, table: Option<&str>) -> Result<Value> {
+    let st = state.lock().await;
+    let pool = st.pool()?;
+    let kind = st.kind()?;
+    let table_filter = table
+        .map(|t| match kind {
+            DbKind::MySQL => format!("AND table_name = {}", quote_literal(kind, t)),
+            DbKind::Postgres => format!("AND c.relname = {}", quote_literal(kind, t)),
+        })
+        .unwrap_or_default();
+
     let sql = match kind {
         DbKind::MySQL => format!(
-            "SELECT column_name, data_type, is_nullable, column_default, \
-             character_maximum_length, column_key, extra \
-             FROM information_schema.columns \
-             WHERE table_schema = DATABASE() AND table_name = '{table}' \
-             ORDER BY ordinal_position"
+            "SELECT CAST(table_name AS CHAR) AS table_name, \
+             CAST(engine AS CHAR) AS engine, \
+             CAST(table_rows AS SIGNED) AS estimated_rows, \
+             CAST(data_length AS SIGNED) AS data_bytes, \
+             CAST(index_length AS SIGNED) AS index_bytes, \
+             CAST(data_length + index_length AS SIGNED) AS total_bytes, \
+             CAST(data_free AS SIGNED) AS free_bytes, \
+             CAST(auto_increment AS SIGNED) AS auto_increment, \
+             CAST(create_time AS CHAR) AS create_time, \
+             CAST(update_time AS CHAR) AS update_time \
+             FROM information_schema.tables \
+             WHERE table_schema = DATABASE() AND table_type = 'BASE TABLE' {table_filter} \
+             ORDER BY data_length + index_length DESC, table_name"
         ),
         DbKind::Postgres => format!(
-            "SELECT column_name, data_type, is_nullable, column_default, \
-             character_maximum_length \
-             FROM information_schema.columns \
-             WHERE table_name = '{table}' \
-             ORDER BY ordinal_position"
+            "SELECT n.nspname::text AS schema, c.relname::text AS table_name, \
+             CASE WHEN c.reltuples < 0 THEN NULL ELSE c.reltuples::bigint END AS estimated_rows, \
+             pg_catalog.pg_table_size(c.oid) AS data_bytes, \
+             pg_catalog.pg_indexes_size(c.oid) AS index_bytes, \
+             pg_catalog.pg_total_relation_size(c.oid) AS total_bytes, \
+             s.n_live_tup AS live_tuples, s.n_dead_tup AS dead_tuples, \
+             s.last_vacuum::text AS last_vacuum, s.last_autovacuum::text AS last_autovacuum, \
+             s.last_analyze::text AS last_analyze, s.last_autoanalyze::text AS last_autoanalyze \
+             FROM pg_catalog.pg_class c \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             LEFT JOIN pg_catalog.pg_stat_user_tables s ON s.relid = c.oid \
+             WHERE c.relkind IN ('r', 'p') \
+               AND n.nspname NOT IN ('pg_catalog','information_schema') \
+               AND n.nspname NOT LIKE 'pg_toast%' {table_filter} \
+             ORDER BY pg_catalog.pg_total_relation_size(c.oid) DESC, c.relname"
         ),
     };
 
     let rows = sqlx::query(&sql).fetch_all(pool).await
-        .map_err(|e| anyhow!("describe_table error: {e}"))?;
+        .map_err(|e| anyhow!("table_stats error: {e}"))?;
 
     if rows.is_empty() {
-        return Err(anyhow!("Table '{table}' not found or has no columns."));
+        if let Some(t) = table {
+            return Err(anyhow!("Table '{t}' not found."));
+        }
     }
 
-    let columns: Vec<Value> = rows.iter().map(row_to_json).collect();
-    Ok(json!({ "table": table, "columns": columns }))
+    let tables: Vec<Value> = rows
+        .iter()
+        .map(|r| {
+            let mut stats = row_to_json(r);
+            if let Some(obj) = stats.as_object_mut() {
+                if let Some(total) = obj.get("total_bytes").and_then(Value::as_i64) {
+                    obj.insert("total_size".into(), json!(human_bytes(total)));
+                }
+            }
+            stats
+        })
+        .collect();
+
+    Ok(json!({
+        "tables": tables,
+        "note": "Row counts are planner estimates; run ANALYZE for fresher numbers."
+    }))
 }
 
 pub async fn get_full_schema(state: &SharedState) -> Result<Value> {
-    let tables_val = list_tables(state).await?;
-    let tables: Vec<String> = tables_val["tables"]
-        .as_array()
-        .unwrap_or(&vec![])
-        .iter()
-        .filter_map(|v| v.as_str().map(String::from))
-        .collect();
+    let mut st = state.lock().await;
+    if let Some(cached) = st.schema_cache.full() {
+        return Ok(cached.clone());
+    }
+    let pool = st.pool()?;
+    let kind = st.kind()?;
+
+    let filter = match kind {
+        DbKind::MySQL => "c.table_schema = DATABASE() AND t.table_type = 'BASE TABLE'",
+        DbKind::Postgres => {
+            "c.table_schema NOT IN ('pg_catalog','information_schema') \
+             AND t.table_type = 'BASE TABLE'"
+        }
+    };
+
+    let rows = sqlx::query(&columns_sql(kind, filter)).fetch_all(pool).await
+        .map_err(|e| anyhow!("get_full_schema error: {e}"))?;
 
     let mut schema = serde_json::Map::new();
-    for table in &tables {
-        match describe_table(state, table).await {
-            Ok(info) => {
-                schema.insert(table.clone(), info["columns"].clone());
+    for row in &rows {
+        let mut col = annotate_column(kind, row_to_json(row));
+        let Some(table) = col
+            .as_object_mut()
+            .and_then(|obj| obj.remove("table_key"))
+            .and_then(|v| v.as_str().map(String::from))
+        else {
+            continue;
+        };
+        if let Value::Array(columns) = schema.entry(table).or_insert_with(|| json!([])) {
+            columns.push(col);
+        }
+    }
+
+    let foreign_keys = fetch_foreign_keys(pool, kind).await?;
+
+    let full = json!({
+        "schema": schema,
+        "table_count": schema.len(),
+        "foreign_keys": foreign_keys
+    });
+    st.schema_cache.put_full(full.clone());
+    Ok(full)
+}
+
+/// Drops any cached schema for the connection and reloads it.
+pub async fn refresh_schema(state: &SharedState) -> Result<Value> {
+    state.lock().await.schema_cache.invalidate();
+    let full = get_full_schema(state).await?;
+    Ok(json!({
+        "message": "Schema cache refreshed.",
+        "table_count": full["table_count"]
+    }))
+}
+
+/// All foreign keys visible to the connection, one entry per constraint with
+/// its (possibly composite) column lists in key order.
+async fn fetch_foreign_keys(pool: &AnyPool, kind: DbKind) -> Result<Vec<Value>> {
+    let sql = match kind {
+        DbKind::MySQL => {
+            "SELECT CAST(k.table_name AS CHAR) AS table_key, \
+             CAST(k.constraint_name AS CHAR) AS name, \
+             CAST(k.column_name AS CHAR) AS column_name, \
+             CAST(k.referenced_table_name AS CHAR) AS referenced_table, \
+             CAST(k.referenced_column_name AS CHAR) AS referenced_column \
+             FROM information_schema.key_column_usage k \
+             WHERE k.table_schema = DATABASE() AND k.referenced_table_name IS NOT NULL \
+             ORDER BY k.table_name, k.constraint_name, k.ordinal_position"
+        }
+        DbKind::Postgres => {
+            "SELECT (CASE WHEN n.nspname = current_schema() THEN c.relname::text \
+                     ELSE n.nspname || '.' || c.relname END)::text AS table_key, \
+             con.conname::text AS name, \
+             a.attname::text AS column_name, \
+             (CASE WHEN rn.nspname = current_schema() THEN rc.relname::text \
+                   ELSE rn.nspname || '.' || rc.relname END)::text AS referenced_table, \
+             ra.attname::text AS referenced_column \
+             FROM pg_catalog.pg_constraint con \
+             JOIN pg_catalog.pg_class c ON c.oid = con.conrelid \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             JOIN pg_catalog.pg_class rc ON rc.oid = con.confrelid \
+             JOIN pg_catalog.pg_namespace rn ON rn.oid = rc.relnamespace \
+             CROSS JOIN LATERAL unnest(con.conkey, con.confkey) WITH ORDINALITY AS k(attnum, refnum, ord) \
+             JOIN pg_catalog.pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum \
+             JOIN pg_catalog.pg_attribute ra ON ra.attrelid = con.confrelid AND ra.attnum = k.refnum \
+             WHERE con.contype = 'f' AND n.nspname NOT IN ('pg_catalog','information_schema') \
+             ORDER BY 1, 2, k.ord"
+        }
+    };
+
+    let rows = sqlx::query(sql).fetch_all(pool).await
+        .map_err(|e| anyhow!("foreign key lookup error: {e}"))?;
+
+    let mut keys: Vec<Value> = Vec::new();
+    for row in &rows {
+        let table: String = row.try_get("table_key")?;
+        let name: String = row.try_get("name")?;
+        let column: String = row.try_get("column_name")?;
+        let referenced_column: String = row.try_get("referenced_column")?;
+
+        let same = keys.last().is_some_and(|k| k["table"] == table && k["name"] == name);
+        if !same {
+            keys.push(json!({
+                "table": table,
+                "name": name,
+                "columns": [],
+                "referenced_table": row.try_get::<String, _>("referenced_table")?,
+                "referenced_columns": []
+            }));
+        }
+        if let Some(last) = keys.last_mut() {
+            if let Some(cols) = last["columns"].as_array_mut() {
+                cols.push(json!(column));
             }
-            Err(e) => {
-                schema.insert(table.clone(), json!({ "error": e.to_string() }));
+            if let Some(cols) = last["referenced_columns"].as_array_mut() {
+                cols.push(json!(referenced_column));
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// A column as it can be read back through the `Any` driver, which only
+/// decodes booleans, integers, floats, text and bytes. `expr` selects the
+/// column, cast to text when its type is anything else.
+pub(crate) struct ReadableColumn {
+    pub name: String,
+    pub data_type: String,
+    /// Lower-cased underlying type name (`int4`, `varchar`, `datetime`, ...).
+    pub base_type: String,
+    pub expr: String,
+    pub binary: bool,
+}
+
+/// Splits an optional `schema.` prefix off a table name.
+pub(crate) fn split_table(table: &str) -> (Option<&str>, &str) {
+    match table.split_once('.') {
+        Some((schema, name)) => (Some(schema), name),
+        None => (None, table),
+    }
+}
+
+/// Quotes a possibly schema-qualified table name for use in `FROM`.
+pub(crate) fn qualified_table(kind: DbKind, table: &str) -> String {
+    match split_table(table) {
+        (Some(schema), name) => format!("{}.{}", quote_ident(kind, schema), quote_ident(kind, name)),
+        (None, name) => quote_ident(kind, name),
+    }
+}
+
+/// `pg_class` filter (aliases `c` / `n`) selecting the table the way an
+/// unqualified name would resolve through `search_path`.
+pub(crate) fn pg_relation_filter(table: &str) -> String {
+    match split_table(table) {
+        (Some(schema), name) => format!(
+            "n.nspname = {} AND c.relname = {}",
+            quote_literal(DbKind::Postgres, schema),
+            quote_literal(DbKind::Postgres, name)
+        ),
+        (None, name) => format!(
+            "c.relname = {} AND pg_catalog.pg_table_is_visible(c.oid)",
+            quote_literal(DbKind::Postgres, name)
+        ),
+    }
+}
+
+/// `information_schema` filter (no alias) selecting a MySQL table.
+pub(crate) fn mysql_table_filter(table: &str) -> String {
+    match split_table(table) {
+        (Some(schema), name) => format!(
+            "table_schema = {} AND table_name = {}",
+            quote_literal(DbKind::MySQL, schema),
+            quote_literal(DbKind::MySQL, name)
+        ),
+        (None, name) => format!(
+            "table_schema = DATABASE() AND table_name = {}",
+            quote_literal(DbKind::MySQL, name)
+        ),
+    }
+}
+
+pub(crate) async fn readable_columns(pool: &AnyPool, kind: DbKind, table: &str) -> Result<Vec<ReadableColumn>> {
+    let sql = match kind {
+        DbKind::MySQL => format!(
+            "SELECT CAST(column_name AS CHAR) AS name, \
+             CAST(column_type AS CHAR) AS data_type, \
+             CAST(data_type AS CHAR) AS base_type \
+             FROM information_schema.columns \
+             WHERE {} ORDER BY ordinal_position",
+            mysql_table_filter(table)
+        ),
+        DbKind::Postgres => format!(
+            "SELECT a.attname::text AS name, \
+             pg_catalog.format_type(a.atttypid, a.atttypmod) AS data_type, \
+             bt.typname::text AS base_type \
+             FROM pg_catalog.pg_attribute a \
+             JOIN pg_catalog.pg_class c ON c.oid = a.attrelid \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             JOIN pg_catalog.pg_type t ON t.oid = a.atttypid \
+             JOIN pg_catalog.pg_type bt \
+               ON bt.oid = CASE WHEN t.typtype = 'd' THEN t.typbasetype ELSE t.oid END \
+             WHERE {} AND a.attnum > 0 AND NOT a.attisdropped \
+             ORDER BY a.attnum",
+            pg_relation_filter(table)
+        ),
+    };
+
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+    if rows.is_empty() {
+        return Err(anyhow!("Table '{table}' not found or has no columns."));
+    }
+
+    let mut columns = Vec::with_capacity(rows.len());
+    for r in &rows {
+        let name: String = r.try_get("name")?;
+        let base_type: String = r.try_get::<String, _>("base_type")?.to_lowercase();
+        let (expr, binary) = readable_expr(kind, &name, &base_type);
+        columns.push(ReadableColumn {
+            name,
+            data_type: r.try_get("data_type")?,
+            base_type,
+            expr,
+            binary,
+        });
+    }
+    Ok(columns)
+}
+
+/// Primary-key columns of a table, in key order; empty when it has none.
+pub(crate) async fn primary_key(pool: &AnyPool, kind: DbKind, table: &str) -> Result<Vec<String>> {
+    let sql = match kind {
+        DbKind::MySQL => format!(
+            "SELECT CAST(column_name AS CHAR) AS name FROM information_schema.statistics \
+             WHERE {} AND index_name = 'PRIMARY' ORDER BY seq_in_index",
+            mysql_table_filter(table)
+        ),
+        DbKind::Postgres => format!(
+            "SELECT a.attname::text AS name \
+             FROM pg_catalog.pg_index i \
+             JOIN pg_catalog.pg_class c ON c.oid = i.indrelid \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             JOIN unnest(i.indkey) WITH ORDINALITY AS k(attnum, ord) ON true \
+             JOIN pg_catalog.pg_attribute a ON a.attrelid = c.oid AND a.attnum = k.attnum \
+             WHERE i.indisprimary AND {} ORDER BY k.ord",
+            pg_relation_filter(table)
+        ),
+    };
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+    rows.iter().map(|r| Ok(r.try_get("name")?)).collect()
+}
+
+/// Selects a column so the `Any` driver can decode it, and whether it is
+/// binary. `base_type` is the lower-cased underlying type name.
+pub(crate) fn readable_expr(kind: DbKind, name: &str, base_type: &str) -> (String, bool) {
+    let quoted = quote_ident(kind, name);
+    match kind {
+        DbKind::MySQL => match base_type {
+            "float" | "double" | "real" => (quoted, false),
+            "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "year" => {
+                (format!("CAST({quoted} AS SIGNED) AS {quoted}"), false)
             }
+            "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => (quoted, true),
+            _ => (format!("CAST({quoted} AS CHAR) AS {quoted}"), false),
+        },
+        DbKind::Postgres => match base_type {
+            "bool" | "int2" | "int4" | "int8" | "float4" | "float8" | "text" | "varchar" => (quoted, false),
+            "bytea" => (quoted, true),
+            _ => (format!("{quoted}::text AS {quoted}"), false),
+        },
+    }
+}
+
+/// A value read through `readable_columns` / `describe_query`, kept typed
+/// for writers that care (copy, export).
+pub(crate) enum Cell {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+/// Opens a native driver connection next to the pool, giving up after
+/// `timeout` as acquiring from the pool would.
+pub(crate) async fn connect_native<C: sqlx::Connection>(url: &str, timeout: Duration) -> Result<C> {
+    tokio::time::timeout(timeout, C::connect(url))
+        .await
+        .map_err(|_| anyhow!("Connection failed: timed out after {}s", timeout.as_secs()))?
+        .map_err(|e| anyhow!("Connection failed: {e}"))
+}
+
+/// Column names and types of an arbitrary query. The `Any` driver refuses to
+/// describe types it cannot decode, so this asks the native driver instead.
+pub(crate) async fn describe_query(url: &str, timeout: Duration, kind: DbKind, sql: &str) -> Result<Vec<ReadableColumn>> {
+    use sqlx::{Connection, Executor};
+
+    let described: Vec<(String, String)> = match kind {
+        DbKind::Postgres => {
+            let mut conn: sqlx::postgres::PgConnection = connect_native(url, timeout).await?;
+            let d = (&mut conn).describe(sql).await?;
+            let cols = d.columns().iter().map(|c| (c.name().to_string(), c.type_info().name().to_string())).collect();
+            conn.close().await.ok();
+            cols
+        }
+        DbKind::MySQL => {
+            let mut conn: sqlx::mysql::MySqlConnection = connect_native(url, timeout).await?;
+            let d = (&mut conn).describe(sql).await?;
+            let cols = d.columns().iter().map(|c| (c.name().to_string(), c.type_info().name().to_string())).collect();
+            conn.close().await.ok();
+            cols
         }
+    };
+
+    let mut columns: Vec<ReadableColumn> = Vec::with_capacity(described.len());
+    for (name, type_name) in described {
+        if columns.iter().any(|c| c.name == name) {
+            return Err(anyhow!("Query returns column '{name}' more than once; give each column a unique alias."));
+        }
+        let base_type = type_name.to_lowercase();
+        let (expr, binary) = readable_expr(kind, &name, &base_type);
+        columns.push(ReadableColumn { name, data_type: base_type.clone(), base_type, expr, binary });
     }
+    Ok(columns)
+}
+
+pub(crate) fn decode_cell(row: &AnyRow, i: usize, col: &ReadableColumn) -> Result<Cell> {
+    let type_name = row.column(i).type_info().name().to_lowercase();
+    let cell = if col.binary || type_name.contains("blob") {
+        row.try_get::<Option<Vec<u8>>, _>(i)?.map(Cell::Bytes)
+    } else if type_name.contains("bool") {
+        row.try_get::<Option<bool>, _>(i)?.map(Cell::Bool)
+    } else if type_name.contains("int") {
+        row.try_get::<Option<i64>, _>(i)?.map(Cell::Int)
+    } else if type_name.contains("real") || type_name.contains("double") || type_name.contains("float") {
+        row.try_get::<Option<f64>, _>(i)?.map(Cell::Float)
+    } else if type_name == "null" {
+        None
+    } else {
+        row.try_get::<Option<String>, _>(i)?.map(Cell::Text)
+    };
+    Ok(cell.unwrap_or(Cell::Null))
+}
 
-    Ok(json!({ "schema": schema, "table_count": tables.len() }))
+/// The planner's row estimate for a table, or `None` if it has never been
+/// analyzed.
+pub(crate) async fn estimated_rows(pool: &AnyPool, kind: DbKind, table: &str) -> Result<Option<i64>> {
+    let sql = match kind {
+        DbKind::MySQL => format!(
+            "SELECT CAST(table_rows AS SIGNED) FROM information_schema.tables WHERE {}",
+            mysql_table_filter(table)
+        ),
+        DbKind::Postgres => format!(
+            "SELECT CASE WHEN c.reltuples < 0 THEN NULL ELSE c.reltuples::bigint END \
+             FROM pg_catalog.pg_class c \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             WHERE {}",
+            pg_relation_filter(table)
+        ),
+    };
+    let row = sqlx::query(&sql).fetch_optional(pool).await?;
+    Ok(row.and_then(|r| r.try_get::<Option<i64>, _>(0).ok().flatten()))
 }
 
 pub async fn get_db_info(state: &SharedState) -> Result<Value> {
@@ -288,12 +1126,19 @@ pub async fn get_db_info(state: &SharedState) -> Result<Value> {
     Ok(json!({
         "connected": true,
         "db_type": st.kind().map(|k| k.label()).unwrap_or("unknown"),
-        "connection": st.url.as_deref().map(redact_url).unwrap_or_default()
+        "connection": st.display_url.as_deref().map(redact_url).unwrap_or_default(),
+        "read_only": st.read_only,
+        "ssh_tunnel": st.tunnel.as_ref().map(|t| t.label.clone()),
+        "pool": {
+            "settings": st.pool_settings.to_json(),
+            "open_connections": st.pool.as_ref().map(|p| p.size()),
+            "idle_connections": st.pool.as_ref().map(|p| p.num_idle()),
+        }
     }))
 }
 
 
-fn row_to_json(row: &sqlx::any::AnyRow) -> Value {
+pub(crate) fn row_to_json(row: &sqlx::any::AnyRow) -> Value {
     let mut map = serde_json::Map::new();
     for col in row.columns() {
         let name = col.name().to_string();
@@ -325,6 +1170,15 @@ fn row_to_json(row: &sqlx::any::AnyRow) -> Value {
                 .flatten()
                 .map(|v| json!(v))
                 .unwrap_or(Value::Null)
+        } else if type_name.contains("blob") {
+            row.try_get::<Option<Vec<u8>>, _>(col.ordinal())
+                .ok()
+                .flatten()
+                .map(|v| match String::from_utf8(v) {
+                    Ok(text) => json!(text),
+                    Err(e) => json!(format!("\\x{}", to_hex(e.as_bytes()))),
+                })
+                .unwrap_or(Value::Null)
         } else {
             row.try_get::<Option<String>, _>(col.ordinal())
                 .ok()
@@ -338,16 +1192,149 @@ fn row_to_json(row: &sqlx::any::AnyRow) -> Value {
     Value::Object(map)
 }
 
-fn redact_url(url: &str) -> String {
-    if let Some(at) = url.rfind('@') {
-        if let Some(slash2) = url.find("://") {
-            let scheme_end = slash2 + 3;
-            let creds = &url[scheme_end..at];
-            if let Some(colon) = creds.find(':') {
-                let user = &creds[..colon];
-                return format!("{}://{}:****@{}", &url[..slash2], user, &url[at + 1..]);
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Formats a byte count with a binary unit, e.g. `12.3 MiB`.
+fn human_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Whether a statement's leading keyword is plain DML, which leaves the
+/// schema alone. Anything else that is not a read (DDL, `DO` blocks,
+/// procedure calls, `SET search_path`, ...) invalidates the schema cache.
+fn is_dml(keyword: &str) -> bool {
+    ["INSERT", "UPDATE", "DELETE", "MERGE", "REPLACE", "TRUNCATE"].contains(&keyword)
+}
+
+/// The first keyword of a statement, upper-cased, after any leading
+/// whitespace and `--` / `#` / `/* */` comments.
+fn leading_keyword(sql: &str) -> String {
+    let mut rest = sql.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("--").or_else(|| rest.strip_prefix('#')) {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after).trim_start();
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after).trim_start();
+        } else {
+            break;
+        }
+    }
+    rest.split(|c: char| !c.is_ascii_alphabetic()).next().unwrap_or("").to_uppercase()
+}
+
+/// Adds `enum_values` / `set_values` to a described column and drops the
+/// helper fields that are null, so the model never has to guess at them.
+fn annotate_column(kind: DbKind, mut col: Value) -> Value {
+    let Some(obj) = col.as_object_mut() else {
+        return col;
+    };
+    match kind {
+        DbKind::MySQL => {
+            let parsed = obj
+                .get("column_type")
+                .and_then(|v| v.as_str())
+                .and_then(parse_mysql_enum);
+            if let Some((key, values)) = parsed {
+                obj.insert(key.to_string(), json!(values));
+            }
+        }
+        DbKind::Postgres => {
+            let parsed = obj
+                .get("enum_values")
+                .and_then(|v| v.as_str())
+                .and_then(|s| serde_json::from_str::<Value>(s).ok());
+            match parsed {
+                Some(values) => {
+                    obj.insert("enum_values".into(), values);
+                }
+                None => {
+                    obj.remove("enum_values");
+                }
             }
+            if obj.get("domain").is_some_and(Value::is_null) {
+                obj.remove("domain");
+            }
+        }
+    }
+    col
+}
+
+/// Parses a MySQL `enum('a','b')` / `set('a','b')` column type into the key
+/// to report it under and its list of allowed values.
+fn parse_mysql_enum(column_type: &str) -> Option<(&'static str, Vec<String>)> {
+    let lower = column_type.to_ascii_lowercase();
+    let (key, body) = if lower.starts_with("enum(") {
+        ("enum_values", &column_type[5..])
+    } else if lower.starts_with("set(") {
+        ("set_values", &column_type[4..])
+    } else {
+        return None;
+    };
+
+    let mut values = Vec::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\'' {
+            continue;
+        }
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    value.push('\'');
+                }
+                '\'' => break,
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        value.push(escaped);
+                    }
+                }
+                other => value.push(other),
+            }
+        }
+        values.push(value);
+    }
+    Some((key, values))
+}
+
+/// Renders a string as a single-quoted SQL literal for the given dialect.
+pub(crate) fn quote_literal(kind: DbKind, value: &str) -> String {
+    let escaped = match kind {
+        DbKind::MySQL => value.replace('\\', "\\\\").replace('\'', "''"),
+        DbKind::Postgres => value.replace('\'', "''"),
+    };
+    format!("'{escaped}'")
+}
+
+/// Quotes an identifier (table, column, schema) for the given dialect.
+pub(crate) fn quote_ident(kind: DbKind, name: &str) -> String {
+    match kind {
+        DbKind::MySQL => format!("`{}`", name.replace('`', "``")),
+        DbKind::Postgres => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+fn redact_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            let _ = parsed.set_password(Some("****"));
+            parsed.to_string()
         }
+        Ok(_) => url.to_string(),
+        Err(_) => "(invalid URL)".to_string(),
     }
-    url.to_string()
 }
@@ -0,0 +1,339 @@
+use crate::db::{quote_ident, quote_literal, DbKind, SharedState};
+use anyhow::{anyhow, Result};
+use sqlx::{AnyPool, Row};
+use std::collections::BTreeMap;
+
+/// Returns `CREATE` statements for one table, or for every table in a schema
+/// when `table` is `None`. `schema` defaults to the connection's current
+/// database (MySQL) or `current_schema()` (PostgreSQL).
+pub async fn get_ddl(state: &SharedState, table: Option<&str>, schema: Option<&str>) -> Result<String> {
+    let st = state.lock().await;
+    let pool = st.pool()?;
+    let kind = st.kind()?;
+
+    match kind {
+        DbKind::MySQL => mysql_ddl(pool, table, schema).await,
+        DbKind::Postgres => postgres_ddl(pool, table, schema).await,
+    }
+}
+
+async fn mysql_ddl(pool: &AnyPool, table: Option<&str>, schema: Option<&str>) -> Result<String> {
+    let qualify = |name: &str| match schema {
+        Some(s) => format!("{}.{}", quote_ident(DbKind::MySQL, s), quote_ident(DbKind::MySQL, name)),
+        None => quote_ident(DbKind::MySQL, name),
+    };
+
+    let tables: Vec<String> = match table {
+        Some(t) => vec![t.to_string()],
+        None => {
+            let schema_expr = schema
+                .map(|s| quote_literal(DbKind::MySQL, s))
+                .unwrap_or_else(|| "DATABASE()".into());
+            let sql = format!(
+                "SELECT table_name FROM information_schema.tables \
+                 WHERE table_schema = {schema_expr} AND table_type = 'BASE TABLE' \
+                 ORDER BY table_name"
+            );
+            sqlx::query(&sql)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .filter_map(|r| r.try_get::<String, _>(0).ok())
+                .collect()
+        }
+    };
+
+    let mut statements = Vec::new();
+    for name in &tables {
+        let row = sqlx::query(&format!("SHOW CREATE TABLE {}", qualify(name)))
+            .fetch_one(pool)
+            .await
+            .map_err(|e| anyhow!("get_ddl error for '{name}': {e}"))?;
+        let create: String = row.try_get(1)?;
+        statements.push(format!("{create};"));
+    }
+
+    if table.is_none() && !statements.is_empty() {
+        statements.insert(0, "SET FOREIGN_KEY_CHECKS = 0;".into());
+        statements.push("SET FOREIGN_KEY_CHECKS = 1;".into());
+    }
+    Ok(statements.join("\n\n"))
+}
+
+/// One table's worth of catalog rows, gathered before rendering.
+#[derive(Default)]
+struct PgTable {
+    qualified: String,
+    partition_key: Option<String>,
+    comment: Option<String>,
+    columns: Vec<PgColumn>,
+    constraints: Vec<String>,
+    foreign_keys: Vec<String>,
+    indexes: Vec<String>,
+}
+
+struct PgColumn {
+    name: String,
+    definition: String,
+    comment: Option<String>,
+}
+
+async fn postgres_ddl(pool: &AnyPool, table: Option<&str>, schema: Option<&str>) -> Result<String> {
+    let schema_expr = schema
+        .map(|s| quote_literal(DbKind::Postgres, s))
+        .unwrap_or_else(|| "current_schema()".into());
+    let filter = match table {
+        Some(t) if schema.is_some() => format!(
+            "n.nspname = {schema_expr} AND c.relname = {}",
+            quote_literal(DbKind::Postgres, t)
+        ),
+        Some(t) => format!(
+            "c.relname = {} AND pg_catalog.pg_table_is_visible(c.oid)",
+            quote_literal(DbKind::Postgres, t)
+        ),
+        None => format!("n.nspname = {schema_expr}"),
+    };
+    let from = "FROM pg_catalog.pg_class c \
+                JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace";
+
+    let mut tables: BTreeMap<i64, PgTable> = BTreeMap::new();
+    let mut order: Vec<i64> = Vec::new();
+
+    let sql = format!(
+        "SELECT c.oid::bigint AS oid, \
+         quote_ident(n.nspname) || '.' || quote_ident(c.relname) AS qualified, \
+         CASE WHEN c.relkind = 'p' THEN pg_catalog.pg_get_partkeydef(c.oid) END AS partition_key, \
+         pg_catalog.obj_description(c.oid, 'pg_class') AS comment \
+         {from} WHERE {filter} AND c.relkind IN ('r', 'p') ORDER BY c.relname"
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await? {
+        let oid: i64 = r.try_get("oid")?;
+        order.push(oid);
+        tables.insert(oid, PgTable {
+            qualified: r.try_get("qualified")?,
+            partition_key: r.try_get("partition_key")?,
+            comment: r.try_get("comment")?,
+            ..Default::default()
+        });
+    }
+
+    if tables.is_empty() {
+        return Err(match table {
+            Some(t) => anyhow!("Table '{t}' not found."),
+            None => anyhow!("No tables found in schema."),
+        });
+    }
+
+    let sql = format!(
+        "SELECT a.attrelid::bigint AS oid, quote_ident(a.attname) AS name, \
+         pg_catalog.format_type(a.atttypid, a.atttypmod) AS type, \
+         a.attnotnull AS not_null, a.attidentity::text AS identity, \
+         a.attgenerated::text AS generated, \
+         pg_catalog.pg_get_expr(d.adbin, d.adrelid) AS default_expr, \
+         pg_catalog.col_description(a.attrelid, a.attnum) AS comment \
+         {from} \
+         JOIN pg_catalog.pg_attribute a ON a.attrelid = c.oid \
+         LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+         WHERE {filter} AND c.relkind IN ('r', 'p') AND a.attnum > 0 AND NOT a.attisdropped \
+         ORDER BY a.attrelid, a.attnum"
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await? {
+        let oid: i64 = r.try_get("oid")?;
+        let Some(t) = tables.get_mut(&oid) else { continue };
+        let name: String = r.try_get("name")?;
+        let mut definition = format!("{name} {}", r.try_get::<String, _>("type")?);
+        let default_expr: Option<String> = r.try_get("default_expr")?;
+        match (
+            r.try_get::<String, _>("identity")?.as_str(),
+            r.try_get::<String, _>("generated")?.as_str(),
+        ) {
+            ("a", _) => definition.push_str(" GENERATED ALWAYS AS IDENTITY"),
+            ("d", _) => definition.push_str(" GENERATED BY DEFAULT AS IDENTITY"),
+            (_, "s") => {
+                if let Some(expr) = &default_expr {
+                    definition.push_str(&format!(" GENERATED ALWAYS AS ({expr}) STORED"));
+                }
+            }
+            _ => {
+                if let Some(expr) = &default_expr {
+                    definition.push_str(&format!(" DEFAULT {expr}"));
+                }
+            }
+        }
+        if r.try_get::<bool, _>("not_null")? {
+            definition.push_str(" NOT NULL");
+        }
+        t.columns.push(PgColumn { name, definition, comment: r.try_get("comment")? });
+    }
+
+    let sql = format!(
+        "SELECT con.conrelid::bigint AS oid, con.contype::text AS contype, \
+         'CONSTRAINT ' || quote_ident(con.conname) || ' ' || \
+         pg_catalog.pg_get_constraintdef(con.oid, true) AS definition \
+         {from} \
+         JOIN pg_catalog.pg_constraint con ON con.conrelid = c.oid \
+         WHERE {filter} AND con.contype IN ('p', 'u', 'c', 'f', 'x') \
+         ORDER BY con.conrelid, \
+         CASE con.contype WHEN 'p' THEN 0 WHEN 'u' THEN 1 WHEN 'x' THEN 2 WHEN 'c' THEN 3 ELSE 4 END, \
+         con.conname"
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await? {
+        let oid: i64 = r.try_get("oid")?;
+        let Some(t) = tables.get_mut(&oid) else { continue };
+        let definition: String = r.try_get("definition")?;
+        if r.try_get::<String, _>("contype")? == "f" {
+            t.foreign_keys.push(definition);
+        } else {
+            t.constraints.push(definition);
+        }
+    }
+
+    let sql = format!(
+        "SELECT i.indrelid::bigint AS oid, pg_catalog.pg_get_indexdef(i.indexrelid) AS definition \
+         {from} \
+         JOIN pg_catalog.pg_index i ON i.indrelid = c.oid \
+         WHERE {filter} AND NOT EXISTS ( \
+           SELECT 1 FROM pg_catalog.pg_constraint con \
+           WHERE con.conindid = i.indexrelid AND con.conrelid = i.indrelid \
+             AND con.contype IN ('p', 'u', 'x')) \
+         ORDER BY i.indrelid, i.indexrelid"
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await? {
+        let oid: i64 = r.try_get("oid")?;
+        if let Some(t) = tables.get_mut(&oid) {
+            t.indexes.push(format!("{};", r.try_get::<String, _>("definition")?));
+        }
+    }
+
+    let mut statements = Vec::new();
+    if table.is_none() {
+        statements.extend(postgres_schema_types(pool, &schema_expr).await?);
+    }
+
+    for oid in &order {
+        let t = &tables[oid];
+        let body: Vec<String> = t
+            .columns
+            .iter()
+            .map(|c| c.definition.clone())
+            .chain(t.constraints.iter().cloned())
+            .map(|line| format!("    {line}"))
+            .collect();
+        let mut create = format!("CREATE TABLE {} (\n{}\n)", t.qualified, body.join(",\n"));
+        if let Some(key) = &t.partition_key {
+            create.push_str(&format!(" PARTITION BY {key}"));
+        }
+        create.push(';');
+        statements.push(create);
+    }
+
+    for oid in &order {
+        let t = &tables[oid];
+        for fk in &t.foreign_keys {
+            statements.push(format!("ALTER TABLE {} ADD {fk};", t.qualified));
+        }
+        statements.extend(t.indexes.iter().cloned());
+        if let Some(comment) = &t.comment {
+            statements.push(format!(
+                "COMMENT ON TABLE {} IS {};",
+                t.qualified,
+                quote_literal(DbKind::Postgres, comment)
+            ));
+        }
+        for c in &t.columns {
+            if let Some(comment) = &c.comment {
+                statements.push(format!(
+                    "COMMENT ON COLUMN {}.{} IS {};",
+                    t.qualified,
+                    c.name,
+                    quote_literal(DbKind::Postgres, comment)
+                ));
+            }
+        }
+    }
+
+    Ok(statements.join("\n\n"))
+}
+
+/// Enum types, domains and free-standing sequences of a schema, which its
+/// tables may depend on and so must be created first.
+async fn postgres_schema_types(pool: &AnyPool, schema_expr: &str) -> Result<Vec<String>> {
+    let mut statements = Vec::new();
+
+    let sql = format!(
+        "SELECT quote_ident(n.nspname) || '.' || quote_ident(t.typname) AS name, \
+         json_agg(e.enumlabel ORDER BY e.enumsortorder)::text AS labels \
+         FROM pg_catalog.pg_type t \
+         JOIN pg_catalog.pg_namespace n ON n.oid = t.typnamespace \
+         JOIN pg_catalog.pg_enum e ON e.enumtypid = t.oid \
+         WHERE n.nspname = {schema_expr} \
+         GROUP BY n.nspname, t.typname ORDER BY t.typname"
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await? {
+        let name: String = r.try_get("name")?;
+        let labels: Vec<String> = serde_json::from_str(&r.try_get::<String, _>("labels")?)?;
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|l| quote_literal(DbKind::Postgres, l))
+            .collect();
+        statements.push(format!("CREATE TYPE {name} AS ENUM ({});", labels.join(", ")));
+    }
+
+    let sql = format!(
+        "SELECT quote_ident(n.nspname) || '.' || quote_ident(t.typname) AS name, \
+         pg_catalog.format_type(t.typbasetype, t.typtypmod) AS base, \
+         t.typnotnull AS not_null, t.typdefault AS default_expr, \
+         (SELECT string_agg('CONSTRAINT ' || quote_ident(con.conname) || ' ' || \
+                 pg_catalog.pg_get_constraintdef(con.oid, true), ' ' ORDER BY con.conname) \
+            FROM pg_catalog.pg_constraint con WHERE con.contypid = t.oid) AS checks \
+         FROM pg_catalog.pg_type t \
+         JOIN pg_catalog.pg_namespace n ON n.oid = t.typnamespace \
+         WHERE n.nspname = {schema_expr} AND t.typtype = 'd' \
+         ORDER BY t.typname"
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await? {
+        let mut create = format!(
+            "CREATE DOMAIN {} AS {}",
+            r.try_get::<String, _>("name")?,
+            r.try_get::<String, _>("base")?
+        );
+        if let Some(default_expr) = r.try_get::<Option<String>, _>("default_expr")? {
+            create.push_str(&format!(" DEFAULT {default_expr}"));
+        }
+        if r.try_get::<bool, _>("not_null")? {
+            create.push_str(" NOT NULL");
+        }
+        if let Some(checks) = r.try_get::<Option<String>, _>("checks")? {
+            create.push_str(&format!(" {checks}"));
+        }
+        create.push(';');
+        statements.push(create);
+    }
+
+    let sql = format!(
+        "SELECT quote_ident(s.schemaname) || '.' || quote_ident(s.sequencename) AS name, \
+         s.data_type::text AS data_type, s.start_value, s.increment_by, \
+         s.min_value, s.max_value, s.cycle \
+         FROM pg_catalog.pg_sequences s \
+         WHERE s.schemaname = {schema_expr} AND NOT EXISTS ( \
+           SELECT 1 FROM pg_catalog.pg_depend d \
+           WHERE d.classid = 'pg_catalog.pg_class'::regclass \
+             AND d.objid = (quote_ident(s.schemaname) || '.' || quote_ident(s.sequencename))::regclass \
+             AND d.deptype = 'i') \
+         ORDER BY s.sequencename"
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await? {
+        statements.push(format!(
+            "CREATE SEQUENCE {} AS {} INCREMENT BY {} MINVALUE {} MAXVALUE {} START WITH {}{};",
+            r.try_get::<String, _>("name")?,
+            r.try_get::<String, _>("data_type")?,
+            r.try_get::<i64, _>("increment_by")?,
+            r.try_get::<i64, _>("min_value")?,
+            r.try_get::<i64, _>("max_value")?,
+            r.try_get::<i64, _>("start_value")?,
+            if r.try_get::<bool, _>("cycle")? { " CYCLE" } else { "" },
+        ));
+    }
+
+    Ok(statements)
+}
@@ -0,0 +1,646 @@
+use crate::db::{quote_ident, DbKind, SharedState};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{AnyPool, Row};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Everything `diff_schema` compares, for every table of one connection.
+/// Tables are keyed like `get_full_schema`: bare names, schema-qualified on
+/// PostgreSQL when outside `current_schema()`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSnapshot {
+    /// `mysql` or `postgres`.
+    pub dialect: String,
+    pub tables: BTreeMap<String, TableDef>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableDef {
+    pub columns: Vec<ColumnDef>,
+    pub indexes: Vec<IndexDef>,
+    pub constraints: Vec<ConstraintDef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnDef {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// `auto_increment` (MySQL) or identity / generated-column details.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<String>,
+}
+
+/// A secondary index — primary keys and constraint-backed unique indexes
+/// on PostgreSQL are reported as constraints instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexDef {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    /// Full `CREATE INDEX` statement, where the server can produce one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintDef {
+    pub name: String,
+    /// `primary_key`, `unique`, `foreign_key`, `check` or `exclusion`.
+    pub kind: String,
+    pub columns: Vec<String>,
+    /// Dialect SQL as it would follow `CONSTRAINT <name>`.
+    pub definition: String,
+}
+
+trait Named {
+    fn name(&self) -> &str;
+}
+
+impl Named for ColumnDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Named for IndexDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Named for ConstraintDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Differences between two snapshots, from the target's point of view:
+/// `added` exists in the source but not the target, `removed` only in the
+/// target — i.e. what it would take to make the target match the source.
+#[derive(Debug, Default, Serialize)]
+pub struct SchemaDiff {
+    pub tables_added: Vec<String>,
+    pub tables_removed: Vec<String>,
+    pub tables_changed: BTreeMap<String, TableDiff>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TableDiff {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns_added: Vec<ColumnDef>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns_removed: Vec<ColumnDef>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns_changed: Vec<Change<ColumnDef>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub indexes_added: Vec<IndexDef>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub indexes_removed: Vec<IndexDef>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub indexes_changed: Vec<Change<IndexDef>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub constraints_added: Vec<ConstraintDef>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub constraints_removed: Vec<ConstraintDef>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub constraints_changed: Vec<Change<ConstraintDef>>,
+}
+
+impl TableDiff {
+    fn is_empty(&self) -> bool {
+        self.columns_added.is_empty()
+            && self.columns_removed.is_empty()
+            && self.columns_changed.is_empty()
+            && self.indexes_added.is_empty()
+            && self.indexes_removed.is_empty()
+            && self.indexes_changed.is_empty()
+            && self.constraints_added.is_empty()
+            && self.constraints_removed.is_empty()
+            && self.constraints_changed.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Change<T> {
+    pub name: String,
+    /// Which attributes differ, e.g. `data_type`, `nullable`, `default`.
+    pub fields: Vec<&'static str>,
+    pub source: T,
+    pub target: T,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.tables_added.is_empty() && self.tables_removed.is_empty() && self.tables_changed.is_empty()
+    }
+
+    /// One line per difference: `+` added, `-` removed, `~` changed.
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for t in &self.tables_added {
+            lines.push(format!("+ table {t}"));
+        }
+        for t in &self.tables_removed {
+            lines.push(format!("- table {t}"));
+        }
+        for (t, d) in &self.tables_changed {
+            for c in &d.columns_added {
+                lines.push(format!("+ column {t}.{} {}", c.name, c.data_type));
+            }
+            for c in &d.columns_removed {
+                lines.push(format!("- column {t}.{}", c.name));
+            }
+            for c in &d.columns_changed {
+                let parts: Vec<String> = c
+                    .fields
+                    .iter()
+                    .map(|f| match *f {
+                        "data_type" => format!("type {} → {}", c.target.data_type, c.source.data_type),
+                        "nullable" => format!(
+                            "{} → {}",
+                            nullability(c.target.nullable),
+                            nullability(c.source.nullable)
+                        ),
+                        "default" => format!(
+                            "default {} → {}",
+                            c.target.default.as_deref().unwrap_or("none"),
+                            c.source.default.as_deref().unwrap_or("none")
+                        ),
+                        other => format!(
+                            "{other} {} → {}",
+                            c.target.extra.as_deref().unwrap_or("none"),
+                            c.source.extra.as_deref().unwrap_or("none")
+                        ),
+                    })
+                    .collect();
+                lines.push(format!("~ column {t}.{}: {}", c.name, parts.join(", ")));
+            }
+            for i in &d.indexes_added {
+                lines.push(format!("+ index {t}.{} ({})", i.name, i.columns.join(", ")));
+            }
+            for i in &d.indexes_removed {
+                lines.push(format!("- index {t}.{}", i.name));
+            }
+            for i in &d.indexes_changed {
+                lines.push(format!("~ index {t}.{}: {}", i.name, i.fields.join(", ")));
+            }
+            for c in &d.constraints_added {
+                lines.push(format!("+ constraint {t}.{} {}", c.name, c.definition));
+            }
+            for c in &d.constraints_removed {
+                lines.push(format!("- constraint {t}.{}", c.name));
+            }
+            for c in &d.constraints_changed {
+                lines.push(format!(
+                    "~ constraint {t}.{}: {} → {}",
+                    c.name, c.target.definition, c.source.definition
+                ));
+            }
+        }
+        lines
+    }
+}
+
+fn nullability(nullable: bool) -> &'static str {
+    if nullable {
+        "NULL"
+    } else {
+        "NOT NULL"
+    }
+}
+
+/// Compares two snapshots; see `SchemaDiff` for the direction.
+pub fn diff(source: &SchemaSnapshot, target: &SchemaSnapshot) -> SchemaDiff {
+    let mut out = SchemaDiff::default();
+    for (name, src) in &source.tables {
+        let Some(tgt) = target.tables.get(name) else {
+            out.tables_added.push(name.clone());
+            continue;
+        };
+        let mut d = TableDiff::default();
+        (d.columns_added, d.columns_removed, d.columns_changed) =
+            diff_named(&src.columns, &tgt.columns, |s, t| {
+                let mut fields = Vec::new();
+                if !s.data_type.eq_ignore_ascii_case(&t.data_type) {
+                    fields.push("data_type");
+                }
+                if s.nullable != t.nullable {
+                    fields.push("nullable");
+                }
+                if s.default != t.default {
+                    fields.push("default");
+                }
+                if s.extra != t.extra {
+                    fields.push("extra");
+                }
+                fields
+            });
+        (d.indexes_added, d.indexes_removed, d.indexes_changed) =
+            diff_named(&src.indexes, &tgt.indexes, |s, t| {
+                let mut fields = Vec::new();
+                if s.columns != t.columns {
+                    fields.push("columns");
+                }
+                if s.unique != t.unique {
+                    fields.push("unique");
+                }
+                if let (Some(a), Some(b)) = (&s.definition, &t.definition) {
+                    if a != b && fields.is_empty() {
+                        fields.push("definition");
+                    }
+                }
+                fields
+            });
+        (d.constraints_added, d.constraints_removed, d.constraints_changed) =
+            diff_named(&src.constraints, &tgt.constraints, |s, t| {
+                let mut fields = Vec::new();
+                if s.kind != t.kind {
+                    fields.push("kind");
+                }
+                if s.definition != t.definition {
+                    fields.push("definition");
+                }
+                fields
+            });
+        if !d.is_empty() {
+            out.tables_changed.insert(name.clone(), d);
+        }
+    }
+    for name in target.tables.keys() {
+        if !source.tables.contains_key(name) {
+            out.tables_removed.push(name.clone());
+        }
+    }
+    out
+}
+
+type NamedDiff<T> = (Vec<T>, Vec<T>, Vec<Change<T>>);
+
+fn diff_named<T, F>(source: &[T], target: &[T], changed: F) -> NamedDiff<T>
+where
+    T: Named + Clone,
+    F: Fn(&T, &T) -> Vec<&'static str>,
+{
+    let mut added = Vec::new();
+    let mut changes = Vec::new();
+    for s in source {
+        match target.iter().find(|t| t.name() == s.name()) {
+            None => added.push(s.clone()),
+            Some(t) => {
+                let fields = changed(s, t);
+                if !fields.is_empty() {
+                    changes.push(Change {
+                        name: s.name().to_string(),
+                        fields,
+                        source: s.clone(),
+                        target: t.clone(),
+                    });
+                }
+            }
+        }
+    }
+    let removed = target
+        .iter()
+        .filter(|t| !source.iter().any(|s| s.name() == t.name()))
+        .cloned()
+        .collect();
+    (added, removed, changes)
+}
+
+/// Compares two snapshots, each taken from a connection or a snapshot file
+/// (`source` / `target` are the labels echoed back). Both must be of the same
+/// database type, or every column type and default would differ.
+pub fn diff_schema(src: &SchemaSnapshot, tgt: &SchemaSnapshot, source: &str, target: &str) -> Result<Value> {
+    if src.kind()? != tgt.kind()? {
+        return Err(anyhow!(
+            "Cannot compare a {} schema with a {} schema; both sides must use the same database type.",
+            src.dialect,
+            tgt.dialect
+        ));
+    }
+    let d = diff(src, tgt);
+    let summary = d.summary();
+    Ok(json!({
+        "source": source,
+        "target": target,
+        "identical": d.is_empty(),
+        "summary": if summary.is_empty() { "Schemas are identical.".to_string() } else { summary.join("\n") },
+        "diff": d
+    }))
+}
+
+/// Reads tables, columns, indexes and constraints for a connection, one
+/// catalog query per object kind.
+pub async fn load_snapshot(state: &SharedState) -> Result<SchemaSnapshot> {
+    let st = state.lock().await;
+    let pool = st.pool()?;
+    let kind = st.kind()?;
+
+    let tables = match kind {
+        DbKind::MySQL => load_mysql(pool).await?,
+        DbKind::Postgres => load_postgres(pool).await?,
+    };
+    Ok(SchemaSnapshot { dialect: dialect_name(kind).into(), tables })
+}
+
+/// Writes a connection's snapshot as pretty JSON, for later use as either
+/// side of `diff_schema` or `generate_migration`. Like exports, the file must
+/// be inside `DB_MCP_EXPORT_DIRS`; an existing snapshot is replaced.
+pub async fn save_snapshot(state: &SharedState, path: &str) -> Result<Value> {
+    let out = crate::export::resolve_output(path, true)?;
+    let snapshot = load_snapshot(state).await?;
+    let body = serde_json::to_string_pretty(&snapshot)?;
+    fs::write(&out, &body).map_err(|e| anyhow!("Cannot write snapshot to '{}': {e}", out.display()))?;
+    Ok(json!({
+        "path": out.display().to_string(),
+        "dialect": snapshot.dialect,
+        "table_count": snapshot.tables.len(),
+        "bytes": body.len()
+    }))
+}
+
+/// Reads a snapshot written by [`save_snapshot`], from inside
+/// `DB_MCP_EXPORT_DIRS`.
+pub fn read_snapshot(path: &str) -> Result<SchemaSnapshot> {
+    let file = crate::export::resolve_input(path)?;
+    let body = fs::read_to_string(&file).map_err(|e| anyhow!("Cannot read snapshot '{path}': {e}"))?;
+    serde_json::from_str(&body).map_err(|e| anyhow!("'{path}' is not a schema snapshot: {e}"))
+}
+
+impl SchemaSnapshot {
+    pub fn kind(&self) -> Result<DbKind> {
+        match self.dialect.as_str() {
+            "mysql" => Ok(DbKind::MySQL),
+            "postgres" => Ok(DbKind::Postgres),
+            other => Err(anyhow!("Unknown snapshot dialect '{other}'")),
+        }
+    }
+}
+
+pub fn dialect_name(kind: DbKind) -> &'static str {
+    match kind {
+        DbKind::MySQL => "mysql",
+        DbKind::Postgres => "postgres",
+    }
+}
+
+fn json_list(raw: Option<String>) -> Vec<String> {
+    raw.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
+}
+
+async fn load_postgres(pool: &AnyPool) -> Result<BTreeMap<String, TableDef>> {
+    let table_key = |ns: &str, rel: &str| {
+        format!(
+            "(CASE WHEN {ns}.nspname = current_schema() THEN {rel}.relname::text \
+             ELSE {ns}.nspname || '.' || {rel}.relname END)::text"
+        )
+    };
+    let user_tables = "c.relkind IN ('r', 'p') \
+                       AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+                       AND n.nspname NOT LIKE 'pg_toast%'";
+    let from = "FROM pg_catalog.pg_class c \
+                JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace";
+    let mut tables: BTreeMap<String, TableDef> = BTreeMap::new();
+
+    let sql = format!(
+        "SELECT {key} AS table_key, a.attname::text AS name, \
+         pg_catalog.format_type(a.atttypid, a.atttypmod) AS data_type, \
+         CASE WHEN a.attnotnull THEN 'NO' ELSE 'YES' END AS nullable, \
+         pg_catalog.pg_get_expr(d.adbin, d.adrelid) AS default_expr, \
+         CASE a.attidentity WHEN 'a' THEN 'GENERATED ALWAYS AS IDENTITY' \
+                            WHEN 'd' THEN 'GENERATED BY DEFAULT AS IDENTITY' \
+              ELSE CASE WHEN a.attgenerated = 's' THEN 'GENERATED ALWAYS (STORED)' END END AS extra \
+         {from} \
+         JOIN pg_catalog.pg_attribute a ON a.attrelid = c.oid \
+         LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+         WHERE {user_tables} AND a.attnum > 0 AND NOT a.attisdropped \
+         ORDER BY 1, a.attnum",
+        key = table_key("n", "c")
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await.map_err(|e| anyhow!("column snapshot error: {e}"))? {
+        let key: String = r.try_get("table_key")?;
+        tables.entry(key).or_default().columns.push(ColumnDef {
+            name: r.try_get("name")?,
+            data_type: r.try_get("data_type")?,
+            nullable: r.try_get::<String, _>("nullable")? == "YES",
+            default: r.try_get("default_expr")?,
+            extra: r.try_get("extra")?,
+        });
+    }
+
+    let sql = format!(
+        "SELECT {key} AS table_key, ic.relname::text AS name, \
+         CASE WHEN i.indisunique THEN 'YES' ELSE 'NO' END AS is_unique, \
+         array_to_json(ARRAY(SELECT pg_catalog.pg_get_indexdef(i.indexrelid, k, true) \
+                             FROM generate_series(1, i.indnkeyatts) k ORDER BY k))::text AS columns, \
+         pg_catalog.pg_get_indexdef(i.indexrelid) AS definition \
+         {from} \
+         JOIN pg_catalog.pg_index i ON i.indrelid = c.oid \
+         JOIN pg_catalog.pg_class ic ON ic.oid = i.indexrelid \
+         WHERE {user_tables} AND NOT EXISTS ( \
+           SELECT 1 FROM pg_catalog.pg_constraint con \
+           WHERE con.conindid = i.indexrelid AND con.conrelid = i.indrelid \
+             AND con.contype IN ('p', 'u', 'x')) \
+         ORDER BY 1, 2",
+        key = table_key("n", "c")
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await.map_err(|e| anyhow!("index snapshot error: {e}"))? {
+        let key: String = r.try_get("table_key")?;
+        tables.entry(key).or_default().indexes.push(IndexDef {
+            name: r.try_get("name")?,
+            columns: json_list(r.try_get("columns")?),
+            unique: r.try_get::<String, _>("is_unique")? == "YES",
+            definition: r.try_get("definition")?,
+        });
+    }
+
+    let sql = format!(
+        "SELECT {key} AS table_key, con.conname::text AS name, \
+         CASE con.contype WHEN 'p' THEN 'primary_key' WHEN 'u' THEN 'unique' \
+                          WHEN 'f' THEN 'foreign_key' WHEN 'c' THEN 'check' \
+                          ELSE 'exclusion' END AS kind, \
+         pg_catalog.pg_get_constraintdef(con.oid, true) AS definition, \
+         array_to_json(ARRAY(SELECT a.attname::text \
+                             FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, ord) \
+                             JOIN pg_catalog.pg_attribute a \
+                               ON a.attrelid = con.conrelid AND a.attnum = k.attnum \
+                             ORDER BY k.ord))::text AS columns \
+         {from} \
+         JOIN pg_catalog.pg_constraint con ON con.conrelid = c.oid \
+         WHERE {user_tables} AND con.contype IN ('p', 'u', 'f', 'c', 'x') \
+         ORDER BY 1, 2",
+        key = table_key("n", "c")
+    );
+    for r in sqlx::query(&sql).fetch_all(pool).await.map_err(|e| anyhow!("constraint snapshot error: {e}"))? {
+        let key: String = r.try_get("table_key")?;
+        tables.entry(key).or_default().constraints.push(ConstraintDef {
+            name: r.try_get("name")?,
+            kind: r.try_get("kind")?,
+            columns: json_list(r.try_get("columns")?),
+            definition: r.try_get("definition")?,
+        });
+    }
+
+    Ok(tables)
+}
+
+/// Rows of `key_column_usage` folded into one foreign key.
+#[derive(Default)]
+struct MysqlForeignKey {
+    columns: Vec<String>,
+    ref_table: String,
+    ref_columns: Vec<String>,
+    on_update: String,
+    on_delete: String,
+}
+
+async fn load_mysql(pool: &AnyPool) -> Result<BTreeMap<String, TableDef>> {
+    let mut tables: BTreeMap<String, TableDef> = BTreeMap::new();
+    let q = |name: &str| quote_ident(DbKind::MySQL, name);
+
+    let sql = "SELECT CAST(c.table_name AS CHAR) AS table_key, \
+               CAST(c.column_name AS CHAR) AS name, \
+               CAST(c.column_type AS CHAR) AS data_type, \
+               CAST(c.is_nullable AS CHAR) AS nullable, \
+               CAST(c.column_default AS CHAR) AS default_expr, \
+               CAST(NULLIF(c.extra, '') AS CHAR) AS extra \
+               FROM information_schema.columns c \
+               JOIN information_schema.tables t \
+                 ON t.table_schema = c.table_schema AND t.table_name = c.table_name \
+               WHERE c.table_schema = DATABASE() AND t.table_type = 'BASE TABLE' \
+               ORDER BY c.table_name, c.ordinal_position";
+    for r in sqlx::query(sql).fetch_all(pool).await.map_err(|e| anyhow!("column snapshot error: {e}"))? {
+        let key: String = r.try_get("table_key")?;
+        tables.entry(key).or_default().columns.push(ColumnDef {
+            name: r.try_get("name")?,
+            data_type: r.try_get("data_type")?,
+            nullable: r.try_get::<String, _>("nullable")? == "YES",
+            default: r.try_get("default_expr")?,
+            extra: r.try_get("extra")?,
+        });
+    }
+
+    // Unique constraints are unique indexes in MySQL; the primary key is
+    // reported as a constraint.
+    let sql = "SELECT CAST(s.table_name AS CHAR) AS table_key, \
+               CAST(s.index_name AS CHAR) AS name, \
+               CAST(CASE WHEN s.non_unique = 0 THEN 'YES' ELSE 'NO' END AS CHAR) AS is_unique, \
+               CAST(COALESCE(s.column_name, '(expression)') AS CHAR) AS column_name \
+               FROM information_schema.statistics s \
+               JOIN information_schema.tables t \
+                 ON t.table_schema = s.table_schema AND t.table_name = s.table_name \
+               WHERE s.table_schema = DATABASE() AND t.table_type = 'BASE TABLE' \
+               ORDER BY s.table_name, s.index_name, s.seq_in_index";
+    for r in sqlx::query(sql).fetch_all(pool).await.map_err(|e| anyhow!("index snapshot error: {e}"))? {
+        let key: String = r.try_get("table_key")?;
+        let name: String = r.try_get("name")?;
+        let column: String = r.try_get("column_name")?;
+        let table = tables.entry(key).or_default();
+        if name == "PRIMARY" {
+            match table.constraints.iter_mut().find(|c| c.kind == "primary_key") {
+                Some(pk) => pk.columns.push(column),
+                None => table.constraints.push(ConstraintDef {
+                    name,
+                    kind: "primary_key".into(),
+                    columns: vec![column],
+                    definition: String::new(),
+                }),
+            }
+            continue;
+        }
+        match table.indexes.iter_mut().find(|i| i.name == name) {
+            Some(index) => index.columns.push(column),
+            None => table.indexes.push(IndexDef {
+                name,
+                columns: vec![column],
+                unique: r.try_get::<String, _>("is_unique")? == "YES",
+                definition: None,
+            }),
+        }
+    }
+    for table in tables.values_mut() {
+        for pk in table.constraints.iter_mut().filter(|c| c.kind == "primary_key") {
+            let cols: Vec<String> = pk.columns.iter().map(|c| q(c)).collect();
+            pk.definition = format!("PRIMARY KEY ({})", cols.join(", "));
+        }
+    }
+
+    let sql = "SELECT CAST(k.table_name AS CHAR) AS table_key, \
+               CAST(k.constraint_name AS CHAR) AS name, \
+               CAST(k.column_name AS CHAR) AS column_name, \
+               CAST(k.referenced_table_name AS CHAR) AS ref_table, \
+               CAST(k.referenced_column_name AS CHAR) AS ref_column, \
+               CAST(r.update_rule AS CHAR) AS update_rule, \
+               CAST(r.delete_rule AS CHAR) AS delete_rule \
+               FROM information_schema.key_column_usage k \
+               JOIN information_schema.referential_constraints r \
+                 ON r.constraint_schema = k.constraint_schema \
+                AND r.constraint_name = k.constraint_name \
+                AND r.table_name = k.table_name \
+               WHERE k.table_schema = DATABASE() AND k.referenced_table_name IS NOT NULL \
+               ORDER BY k.table_name, k.constraint_name, k.ordinal_position";
+    let mut foreign: BTreeMap<(String, String), MysqlForeignKey> = BTreeMap::new();
+    for r in sqlx::query(sql).fetch_all(pool).await.map_err(|e| anyhow!("constraint snapshot error: {e}"))? {
+        let fk = foreign.entry((r.try_get("table_key")?, r.try_get("name")?)).or_default();
+        fk.columns.push(r.try_get("column_name")?);
+        fk.ref_table = r.try_get("ref_table")?;
+        fk.ref_columns.push(r.try_get("ref_column")?);
+        fk.on_update = r.try_get("update_rule")?;
+        fk.on_delete = r.try_get("delete_rule")?;
+    }
+    for ((table, name), fk) in foreign {
+        let quoted: Vec<String> = fk.columns.iter().map(|c| q(c)).collect();
+        let ref_quoted: Vec<String> = fk.ref_columns.iter().map(|c| q(c)).collect();
+        let mut definition = format!(
+            "FOREIGN KEY ({}) REFERENCES {} ({})",
+            quoted.join(", "),
+            q(&fk.ref_table),
+            ref_quoted.join(", ")
+        );
+        for (clause, rule) in [("ON DELETE", fk.on_delete), ("ON UPDATE", fk.on_update)] {
+            if rule != "RESTRICT" && rule != "NO ACTION" {
+                definition.push_str(&format!(" {clause} {rule}"));
+            }
+        }
+        tables.entry(table).or_default().constraints.push(ConstraintDef {
+            name,
+            kind: "foreign_key".into(),
+            columns: fk.columns,
+            definition,
+        });
+    }
+
+    // CHECK constraints are only exposed by MySQL 8.0.16+ / MariaDB 10.2+;
+    // older servers lack the table (ER_UNKNOWN_TABLE, SQLSTATE 42S02) and
+    // simply have none to report. Other errors are real.
+    let sql = "SELECT CAST(t.table_name AS CHAR) AS table_key, \
+               CAST(c.constraint_name AS CHAR) AS name, \
+               CAST(c.check_clause AS CHAR) AS check_clause \
+               FROM information_schema.check_constraints c \
+               JOIN information_schema.table_constraints t \
+                 ON t.constraint_schema = c.constraint_schema \
+                AND t.constraint_name = c.constraint_name \
+               WHERE t.table_schema = DATABASE() AND t.constraint_type = 'CHECK' \
+               ORDER BY t.table_name, c.constraint_name";
+    let rows = match sqlx::query(sql).fetch_all(pool).await {
+        Ok(rows) => rows,
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("42S02") => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    for r in rows {
+        let key: String = r.try_get("table_key")?;
+        tables.entry(key).or_default().constraints.push(ConstraintDef {
+            name: r.try_get("name")?,
+            kind: "check".into(),
+            columns: Vec::new(),
+            definition: format!("CHECK ({})", r.try_get::<String, _>("check_clause")?),
+        });
+    }
+
+    Ok(tables)
+}
@@ -0,0 +1,288 @@
+use crate::config::{canonical_option, check_option, home_dir, Entry};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A connection found in a client configuration file or the environment,
+/// and where it came from.
+pub struct Discovered {
+    pub entry: Entry,
+    pub source: String,
+}
+
+/// Reads the connections set up for the standard clients: `pg_service.conf`,
+/// `~/.pgpass`, `PG*` variables, `~/.my.cnf` and `MYSQL_*` variables. Files
+/// that are missing or unreadable are skipped.
+pub fn discover() -> Vec<Discovered> {
+    let pgpass = PgPass::load();
+    let mut found = Vec::new();
+    for path in pg_service_files() {
+        found.extend(pg_services(&path, &pgpass));
+    }
+    found.extend(pgpass.entries());
+    found.extend(pg_env(&pgpass));
+    if let Some(path) = home_dir().map(|h| h.join(".my.cnf")) {
+        found.extend(my_cnf(&path));
+    }
+    found.extend(mysql_env());
+    found
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+pub(crate) fn os_user() -> String {
+    env("USER").or_else(|| env("USERNAME")).unwrap_or_default()
+}
+
+/// `[section]` / `key = value` files. Keys are lower-cased with `-` read as
+/// `_`; values lose surrounding quotes.
+fn parse_ini(text: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') || line.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((name.trim().to_string(), Vec::new()));
+            continue;
+        }
+        let Some((_, keys)) = sections.last_mut() else { continue };
+        let (key, value) = line.split_once('=').unwrap_or((line, ""));
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+            .unwrap_or(value);
+        keys.push((key.trim().to_lowercase().replace('-', "_"), value.to_string()));
+    }
+    sections
+}
+
+/// The connection options among a client file's keys.
+fn options(keys: &[(String, String)], mysql: bool) -> BTreeMap<String, String> {
+    keys.iter()
+        .map(|(k, v)| canonical_option(mysql, k, v))
+        .filter(|(k, v)| !v.is_empty() && check_option(if mysql { "mysql" } else { "postgres" }, k, v).is_ok())
+        .collect()
+}
+
+fn get<'a>(keys: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    keys.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_str()).filter(|v| !v.is_empty())
+}
+
+/// `~/.pgpass` (or `PGPASSFILE`): `host:port:database:user:password` lines,
+/// where `*` matches anything and `\` escapes `:` and `\`.
+struct PgPass {
+    lines: Vec<[String; 5]>,
+}
+
+impl PgPass {
+    fn load() -> Self {
+        let path = env("PGPASSFILE").map(PathBuf::from).or_else(|| home_dir().map(|h| h.join(".pgpass")));
+        let problem = "other users can access it, which PostgreSQL clients refuse too (chmod 600 it)";
+        let Some(text) = path.as_deref().and_then(|p| read_private(p, 0o077, problem)) else {
+            return Self { lines: Vec::new() };
+        };
+        let lines = text
+            .lines()
+            .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+            .filter_map(|l| split_pgpass(l).try_into().ok())
+            .collect();
+        Self { lines }
+    }
+
+    /// Password for a connection, from the first line that matches it. A
+    /// socket connection matches `localhost`, as in `libpq`.
+    fn password(&self, host: &str, port: u16, database: &str, user: &str) -> Option<String> {
+        let host = if host.starts_with('/') { "localhost" } else { host };
+        let port = port.to_string();
+        let wanted = [host, port.as_str(), database, user];
+        self.lines
+            .iter()
+            .find(|l| l.iter().zip(wanted).all(|(field, value)| field == "*" || field == value))
+            .map(|l| l[4].clone())
+    }
+
+    /// One connection per line that names a specific host and user.
+    fn entries(&self) -> Vec<Discovered> {
+        self.lines
+            .iter()
+            .filter(|[host, _, _, user, _]| host != "*" && user != "*")
+            .filter_map(|[host, port, database, user, password]| {
+                let port = if port == "*" { 5432 } else { port.parse().ok()? };
+                let database = if database == "*" { String::new() } else { database.clone() };
+                let name = format!("pgpass:{user}@{host}:{port}/{}", if database.is_empty() { user } else { &database });
+                let entry = Entry::new(name, host.clone(), port, user.clone(), password.clone(), "postgres".into(), database);
+                Some(Discovered { entry, source: "~/.pgpass".into() })
+            })
+            .collect()
+    }
+}
+
+fn split_pgpass(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' if fields.len() < 5 => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Reads a credentials file, skipping it (as the client itself would) when
+/// its mode has any of the `refused` bits: libpq ignores a `.pgpass` other
+/// users can read or write, MySQL only an option file anyone can write.
+fn read_private(path: &Path, refused: u32, problem: &str) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path).ok()?.permissions().mode();
+        if mode & refused != 0 {
+            eprintln!("db-mcp: ignoring '{}': {problem}", path.display());
+            return None;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (refused, problem);
+    Some(text)
+}
+
+/// `PGSERVICEFILE` (or `~/.pg_service.conf`), then the system-wide file in
+/// `PGSYSCONFDIR`.
+fn pg_service_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = env("PGSERVICEFILE")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".pg_service.conf")))
+        .into_iter()
+        .collect();
+    files.extend(env("PGSYSCONFDIR").map(|d| Path::new(&d).join("pg_service.conf")));
+    files
+}
+
+fn pg_services(path: &Path, pgpass: &PgPass) -> Vec<Discovered> {
+    let Ok(text) = std::fs::read_to_string(path) else { return Vec::new() };
+    parse_ini(&text)
+        .into_iter()
+        .filter_map(|(service, keys)| {
+            let host = get(&keys, "host").or(get(&keys, "hostaddr")).unwrap_or("localhost").to_string();
+            let port = get(&keys, "port").map_or(Some(5432), |p| p.parse().ok())?;
+            let user = get(&keys, "user").map(str::to_string).unwrap_or_else(os_user);
+            let database = get(&keys, "dbname").unwrap_or(&user).to_string();
+            let password = get(&keys, "password")
+                .map(str::to_string)
+                .or_else(|| pgpass.password(&host, port, &database, &user))
+                .unwrap_or_default();
+            let entry = Entry::new(format!("pg_service:{service}"), host, port, user, password, "postgres".into(), database)
+                .with_options(options(&keys, false))
+                .ok()?;
+            Some(Discovered { entry, source: path.display().to_string() })
+        })
+        .collect()
+}
+
+/// The connection `psql` would make from `PGHOST`, `PGPORT`, `PGUSER`,
+/// `PGDATABASE` and `PGPASSWORD`, when any of the first four is set.
+fn pg_env(pgpass: &PgPass) -> Option<Discovered> {
+    if ["PGHOST", "PGPORT", "PGUSER", "PGDATABASE"].iter().all(|v| env(v).is_none()) {
+        return None;
+    }
+    let host = env("PGHOST").unwrap_or_else(|| "localhost".into());
+    let port = env("PGPORT").map_or(Some(5432), |p| p.parse().ok())?;
+    let user = env("PGUSER").unwrap_or_else(os_user);
+    let database = env("PGDATABASE").unwrap_or_else(|| user.clone());
+    // Keep the reference, so a changed PGPASSWORD is picked up on connect.
+    let password = if env("PGPASSWORD").is_some() {
+        "env:PGPASSWORD".to_string()
+    } else {
+        pgpass.password(&host, port, &database, &user).unwrap_or_default()
+    };
+    let keys: Vec<(String, String)> = [
+        ("PGSSLMODE", "sslmode"),
+        ("PGSSLROOTCERT", "sslrootcert"),
+        ("PGSSLCERT", "sslcert"),
+        ("PGSSLKEY", "sslkey"),
+        ("PGAPPNAME", "application_name"),
+        ("PGCONNECT_TIMEOUT", "connect_timeout"),
+    ]
+    .iter()
+    .filter_map(|(var, key)| Some((key.to_string(), env(var)?)))
+    .collect();
+    let entry = Entry::new("pg-env".into(), host, port, user, password, "postgres".into(), database)
+        .with_options(options(&keys, false))
+        .ok()?;
+    Some(Discovered { entry, source: "PG* environment variables".into() })
+}
+
+/// `[client]` and `[mysql]` make one connection; each `[client<suffix>]`
+/// group (for `--defaults-group-suffix`) layers over `[client]` as another.
+fn my_cnf(path: &Path) -> Vec<Discovered> {
+    let Some(text) = read_private(path, 0o002, "it is world-writable, which MySQL clients refuse too (chmod o-w it)") else { return Vec::new() };
+    let sections = parse_ini(&text);
+    let base: Vec<(String, String)> = sections
+        .iter()
+        .filter(|(name, _)| name == "client" || name == "mysql")
+        .flat_map(|(_, keys)| keys.clone())
+        .collect();
+    let mut groups = vec![("client".to_string(), base.clone())];
+    for (name, keys) in &sections {
+        if name.starts_with("client") && name != "client" {
+            groups.push((name.clone(), base.iter().chain(keys).cloned().collect()));
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, keys)| ["host", "socket", "user", "password", "database"].iter().any(|k| get(keys, k).is_some()))
+        .filter_map(|(group, keys)| {
+            let port = get(&keys, "port").map_or(Some(3306), |p| p.parse().ok())?;
+            // Like the client, use the socket unless a host other than
+            // localhost is named.
+            let host = match (get(&keys, "host"), get(&keys, "socket")) {
+                (None | Some("localhost"), Some(socket)) => socket,
+                (host, _) => host.unwrap_or("localhost"),
+            };
+            let entry = Entry::new(
+                format!("my.cnf:{group}"),
+                host.to_string(),
+                port,
+                get(&keys, "user").map(str::to_string).unwrap_or_else(os_user),
+                get(&keys, "password").unwrap_or_default().to_string(),
+                "mysql".into(),
+                get(&keys, "database").unwrap_or_default().to_string(),
+            )
+            .with_options(options(&keys, true))
+            .ok()?;
+            Some(Discovered { entry, source: path.display().to_string() })
+        })
+        .collect()
+}
+
+/// `MYSQL_HOST`, `MYSQL_TCP_PORT`, `MYSQL_UNIX_PORT` (socket), `MYSQL_USER`,
+/// `MYSQL_PWD` and `MYSQL_DATABASE`, when a host, socket, user or database
+/// is set.
+fn mysql_env() -> Option<Discovered> {
+    if ["MYSQL_HOST", "MYSQL_UNIX_PORT", "MYSQL_USER", "MYSQL_DATABASE"].iter().all(|v| env(v).is_none()) {
+        return None;
+    }
+    let host = match (env("MYSQL_HOST"), env("MYSQL_UNIX_PORT")) {
+        (None, Some(socket)) => socket,
+        (Some(host), Some(socket)) if host == "localhost" => socket,
+        (host, _) => host.unwrap_or_else(|| "localhost".into()),
+    };
+    let port = env("MYSQL_TCP_PORT").map_or(Some(3306), |p| p.parse().ok())?;
+    let entry = Entry::new(
+        "mysql-env".into(),
+        host,
+        port,
+        env("MYSQL_USER").unwrap_or_else(os_user),
+        if env("MYSQL_PWD").is_some() { "env:MYSQL_PWD".into() } else { String::new() },
+        "mysql".into(),
+        env("MYSQL_DATABASE").unwrap_or_default(),
+    );
+    Some(Discovered { entry, source: "MYSQL_* environment variables".into() })
+}
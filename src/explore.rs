@@ -0,0 +1,563 @@
+use crate::db::{
+    estimated_rows, pg_relation_filter, qualified_table, quote_ident, readable_columns, row_to_json,
+    DbKind, ReadableColumn, SharedState,
+};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use sqlx::{AnyPool, Row};
+
+/// Tables at or below this many (estimated) rows are sampled exactly with
+/// `ORDER BY random()`; larger ones use a cheap probabilistic scan.
+const EXACT_SAMPLE_MAX_ROWS: i64 = 10_000;
+
+/// Above this many rows PostgreSQL samples whole pages (`SYSTEM`) instead of
+/// individual rows (`BERNOULLI`), trading randomness for not scanning it all.
+const ROW_SAMPLE_MAX_ROWS: i64 = 1_000_000;
+
+/// Returns `limit` randomly chosen rows, with long values truncated to
+/// `max_value_length` characters and binary values replaced by their size.
+pub async fn sample_table(state: &SharedState, table: &str, limit: u32, filter: Option<&str>, max_value_length: usize,) -> Result<Value> {
+    let st = state.lock().await;
+    let pool = st.pool()?;
+    let kind = st.kind()?;
+
+    let columns = readable_columns(pool, kind, table).await?;
+    let select_list: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            if !c.binary {
+                return c.expr.clone();
+            }
+            let quoted = quote_ident(kind, &c.name);
+            match kind {
+                DbKind::MySQL => format!("CONCAT('<', LENGTH({quoted}), ' bytes>') AS {quoted}"),
+                DbKind::Postgres => format!("'<' || octet_length({quoted}) || ' bytes>' AS {quoted}"),
+            }
+        })
+        .collect();
+    let select_list = select_list.join(", ");
+    let from = qualified_table(kind, table);
+    let where_clause = filter.map(|f| format!("WHERE ({f})")).unwrap_or_default();
+    let estimate = estimated_rows(pool, kind, table).await?;
+
+    let random = match kind {
+        DbKind::MySQL => "RAND()",
+        DbKind::Postgres => "random()",
+    };
+    let (method, rows) = match estimate {
+        Some(total) if total > EXACT_SAMPLE_MAX_ROWS => {
+            // Oversample so a selective filter still leaves enough rows, and
+            // widen the sample if it still comes back short (SYSTEM sampling
+            // picks whole pages, so small fractions can miss entirely).
+            let factor = if filter.is_some() { 50.0 } else { 5.0 };
+            let mut fraction = (limit as f64 * factor / total as f64).min(1.0);
+            let sampler = if total > ROW_SAMPLE_MAX_ROWS { "SYSTEM" } else { "BERNOULLI" };
+            let mut sampled = None;
+            for _ in 0..3 {
+                let (method, sql) = match kind {
+                    DbKind::Postgres => (
+                        "tablesample",
+                        format!(
+                            "SELECT {select_list} FROM {from} TABLESAMPLE {sampler} ({:.6}) {where_clause} \
+                             ORDER BY random() LIMIT {limit}",
+                            fraction * 100.0
+                        ),
+                    ),
+                    DbKind::MySQL => (
+                        "random_scan",
+                        format!(
+                            "SELECT {select_list} FROM {from} WHERE RAND() < {fraction:.8}{} LIMIT {limit}",
+                            filter.map(|f| format!(" AND ({f})")).unwrap_or_default()
+                        ),
+                    ),
+                };
+                let rows = sqlx::query(&sql)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(|e| anyhow!("sample_table error: {e}"))?;
+                if rows.len() >= limit as usize {
+                    sampled = Some((method, rows));
+                    break;
+                }
+                if fraction >= 1.0 {
+                    break;
+                }
+                fraction = (fraction * 10.0).min(1.0);
+            }
+            match sampled {
+                Some(found) => found,
+                None => {
+                    // A very selective filter — fall back to a plain scan.
+                    let sql = format!("SELECT {select_list} FROM {from} {where_clause} LIMIT {limit}");
+                    let rows = sqlx::query(&sql)
+                        .fetch_all(pool)
+                        .await
+                        .map_err(|e| anyhow!("sample_table error: {e}"))?;
+                    ("first_rows", rows)
+                }
+            }
+        }
+        _ => {
+            let sql = format!(
+                "SELECT {select_list} FROM {from} {where_clause} ORDER BY {random} LIMIT {limit}"
+            );
+            let rows = sqlx::query(&sql)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| anyhow!("sample_table error: {e}"))?;
+            ("random", rows)
+        }
+    };
+
+    let mut truncated = 0usize;
+    let rows: Vec<Value> = rows
+        .iter()
+        .map(|r| {
+            let mut row = row_to_json(r);
+            if let Some(obj) = row.as_object_mut() {
+                for value in obj.values_mut() {
+                    if truncate_value(value, max_value_length) {
+                        truncated += 1;
+                    }
+                }
+            }
+            row
+        })
+        .collect();
+
+    Ok(json!({
+        "table": table,
+        "method": method,
+        "estimated_rows": estimate,
+        "columns": columns.iter().map(|c| json!({ "name": c.name, "data_type": c.data_type })).collect::<Vec<_>>(),
+        "rows": rows,
+        "row_count": rows.len(),
+        "truncated_values": truncated
+    }))
+}
+
+/// Shortens a string value to `max_chars`, returning whether it was cut.
+fn truncate_value(value: &mut Value, max_chars: usize) -> bool {
+    let Value::String(s) = value else {
+        return false;
+    };
+    match s.char_indices().nth(max_chars) {
+        Some((cut, _)) => {
+            let total = s.chars().count();
+            s.truncate(cut);
+            s.push_str(&format!("… [{total} chars]"));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Tables estimated above this many rows are profiled over a sample of
+/// roughly `PROFILE_SAMPLE_ROWS` rows unless a full scan is requested.
+const PROFILE_FULL_SCAN_MAX_ROWS: i64 = 1_000_000;
+const PROFILE_SAMPLE_ROWS: f64 = 200_000.0;
+
+/// Where `profile_columns` reads its numbers from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProfileSource {
+    /// `pg_stats` when available on PostgreSQL, else a full scan or a sample
+    /// depending on table size.
+    Auto,
+    Stats,
+    Full,
+    Sample,
+}
+
+impl ProfileSource {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "stats" => Ok(Self::Stats),
+            "full" => Ok(Self::Full),
+            "sample" => Ok(Self::Sample),
+            other => Err(anyhow!("Unknown source '{other}'. Use auto, stats, full or sample.")),
+        }
+    }
+}
+
+/// How a column can be aggregated.
+#[derive(Clone, Copy, PartialEq)]
+enum Category {
+    Numeric,
+    Temporal,
+    Text,
+    Boolean,
+    Binary,
+    Other,
+}
+
+fn categorize(kind: DbKind, base_type: &str) -> Category {
+    match kind {
+        DbKind::Postgres => match base_type {
+            "int2" | "int4" | "int8" | "float4" | "float8" | "numeric" | "money" | "oid" => Category::Numeric,
+            "date" | "time" | "timetz" | "timestamp" | "timestamptz" | "interval" => Category::Temporal,
+            "text" | "varchar" | "bpchar" | "name" | "citext" => Category::Text,
+            "bool" => Category::Boolean,
+            "bytea" => Category::Binary,
+            _ => Category::Other,
+        },
+        DbKind::MySQL => match base_type {
+            "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "decimal" | "numeric"
+            | "float" | "double" | "real" | "year" | "bit" => Category::Numeric,
+            "date" | "time" | "datetime" | "timestamp" => Category::Temporal,
+            "char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext" | "enum" | "set" => {
+                Category::Text
+            }
+            "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => Category::Binary,
+            _ => Category::Other,
+        },
+    }
+}
+
+/// Computes per-column statistics: null ratio, distinct count, min / max,
+/// the most frequent values and, for strings, length statistics.
+pub async fn profile_columns(state: &SharedState, table: &str, only: &[String], top_k: u32, source: ProfileSource,) -> Result<Value> {
+    let st = state.lock().await;
+    let pool = st.pool()?;
+    let kind = st.kind()?;
+
+    let mut columns = readable_columns(pool, kind, table).await?;
+    if !only.is_empty() {
+        if let Some(missing) = only.iter().find(|o| !columns.iter().any(|c| &c.name == *o)) {
+            return Err(anyhow!("Column '{missing}' not found in '{table}'."));
+        }
+        columns.retain(|c| only.contains(&c.name));
+    }
+    let estimate = estimated_rows(pool, kind, table).await?;
+
+    if kind == DbKind::Postgres && matches!(source, ProfileSource::Auto | ProfileSource::Stats) {
+        if let Some(profile) = profile_from_pg_stats(pool, table, &columns, estimate, top_k).await? {
+            return Ok(profile);
+        }
+        if source == ProfileSource::Stats {
+            return Err(anyhow!(
+                "No planner statistics for '{table}'. Run ANALYZE, or use source 'full' or 'sample'."
+            ));
+        }
+    } else if source == ProfileSource::Stats {
+        return Err(anyhow!("source 'stats' is only available on PostgreSQL."));
+    }
+
+    let sample = match source {
+        ProfileSource::Sample => true,
+        ProfileSource::Full => false,
+        _ => estimate.is_some_and(|n| n > PROFILE_FULL_SCAN_MAX_ROWS),
+    };
+    let from = qualified_table(kind, table);
+    let base = if sample {
+        let fraction = estimate
+            .map(|n| (PROFILE_SAMPLE_ROWS / n.max(1) as f64).min(1.0))
+            .unwrap_or(0.1);
+        // The aggregate and each top-values query read the sample again; a
+        // fixed seed makes them all see the same rows.
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() & 0x7fff_ffff)
+            .unwrap_or(1);
+        match kind {
+            DbKind::Postgres => format!(
+                "(SELECT * FROM {from} TABLESAMPLE SYSTEM ({:.6}) REPEATABLE ({seed})) AS profiled",
+                fraction * 100.0
+            ),
+            DbKind::MySQL => format!("(SELECT * FROM {from} WHERE RAND({seed}) < {fraction:.8}) AS profiled"),
+        }
+    } else {
+        format!("{from} AS profiled")
+    };
+
+    let to_text = |expr: &str| match kind {
+        DbKind::MySQL => format!("CAST({expr} AS CHAR)"),
+        DbKind::Postgres => format!("CAST({expr} AS TEXT)"),
+    };
+    let length = |expr: &str| match kind {
+        DbKind::MySQL => format!("CHAR_LENGTH({expr})"),
+        DbKind::Postgres => format!("length({expr})"),
+    };
+    let as_double = |expr: &str| match kind {
+        DbKind::MySQL => format!("({expr}) + 0E0"),
+        DbKind::Postgres => format!("CAST({expr} AS DOUBLE PRECISION)"),
+    };
+
+    let mut aggregates = vec!["COUNT(*) AS total_rows".to_string()];
+    for (i, c) in columns.iter().enumerate() {
+        let q = quote_ident(kind, &c.name);
+        let category = categorize(kind, &c.base_type);
+        // Types without equality (json, geometry, ...) are compared as text.
+        let distinct_expr = if category == Category::Other { to_text(&q) } else { q.clone() };
+        aggregates.push(format!("COUNT({q}) AS non_null_{i}"));
+        aggregates.push(format!("COUNT(DISTINCT {distinct_expr}) AS distinct_{i}"));
+        if matches!(category, Category::Numeric | Category::Temporal | Category::Text) {
+            aggregates.push(format!("{} AS min_{i}", to_text(&format!("MIN({q})"))));
+            aggregates.push(format!("{} AS max_{i}", to_text(&format!("MAX({q})"))));
+        }
+        if category == Category::Text {
+            aggregates.push(format!("MIN({}) AS min_len_{i}", length(&q)));
+            aggregates.push(format!("MAX({}) AS max_len_{i}", length(&q)));
+            aggregates.push(format!("{} AS avg_len_{i}", as_double(&format!("AVG({})", length(&q)))));
+        }
+    }
+    let sql = format!("SELECT {} FROM {base}", aggregates.join(", "));
+    let row = sqlx::query(&sql)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow!("profile_columns error: {e}"))?;
+    let agg = row_to_json(&row);
+    let total = agg["total_rows"].as_i64().unwrap_or(0);
+
+    let mut profiles = Vec::with_capacity(columns.len());
+    for (i, c) in columns.iter().enumerate() {
+        let category = categorize(kind, &c.base_type);
+        let non_null = agg[format!("non_null_{i}")].as_i64().unwrap_or(0);
+        let mut profile = json!({
+            "name": c.name,
+            "data_type": c.data_type,
+            "null_ratio": if total > 0 { json!((total - non_null) as f64 / total as f64) } else { Value::Null },
+            "distinct_count": agg[format!("distinct_{i}")],
+        });
+        if matches!(category, Category::Numeric | Category::Temporal | Category::Text) {
+            for bound in ["min", "max"] {
+                let mut value = agg[format!("{bound}_{i}")].clone();
+                truncate_value(&mut value, 100);
+                profile[bound] = value;
+            }
+        }
+        if category == Category::Text {
+            profile["length"] = json!({
+                "min": agg[format!("min_len_{i}")],
+                "max": agg[format!("max_len_{i}")],
+                "avg": agg[format!("avg_len_{i}")],
+            });
+        }
+        if category != Category::Binary && top_k > 0 {
+            let value = to_text(&quote_ident(kind, &c.name));
+            let sql = format!(
+                "SELECT {value} AS value, COUNT(*) AS count FROM {base} \
+                 WHERE {q} IS NOT NULL GROUP BY {value} ORDER BY COUNT(*) DESC, 1 LIMIT {top_k}",
+                q = quote_ident(kind, &c.name)
+            );
+            let rows = sqlx::query(&sql)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| anyhow!("profile_columns error on '{}': {e}", c.name))?;
+            let mut top: Vec<Value> = rows.iter().map(row_to_json).collect();
+            for v in top.iter_mut() {
+                if let Some(value) = v.get_mut("value") {
+                    truncate_value(value, 100);
+                }
+            }
+            profile["top_values"] = json!(top);
+        }
+        profiles.push(profile);
+    }
+
+    Ok(json!({
+        "table": table,
+        "source": if sample { "sample" } else { "full" },
+        "rows_scanned": total,
+        "estimated_rows": estimate,
+        "columns": profiles,
+        "note": if sample {
+            "Computed over a random sample; distinct counts and top values are for the sample only."
+        } else {
+            "Exact values computed over the whole table."
+        }
+    }))
+}
+
+/// Builds the profile from PostgreSQL planner statistics, or returns `None`
+/// if the table has not been analyzed.
+async fn profile_from_pg_stats(pool: &AnyPool, table: &str, columns: &[ReadableColumn], estimate: Option<i64>, top_k: u32,) -> Result<Option<Value>> {
+    let sql = format!(
+        "SELECT s.attname::text AS name, s.null_frac::float8 AS null_frac, \
+         s.n_distinct::float8 AS n_distinct, s.avg_width AS avg_width, \
+         array_to_json(s.most_common_vals::text::text[])::text AS common_values, \
+         array_to_json(s.most_common_freqs)::text AS common_freqs, \
+         (s.histogram_bounds::text::text[])[1] AS histogram_min, \
+         (s.histogram_bounds::text::text[])[cardinality(s.histogram_bounds::text::text[])] AS histogram_max \
+         FROM pg_catalog.pg_stats s \
+         JOIN pg_catalog.pg_namespace n ON n.nspname = s.schemaname \
+         JOIN pg_catalog.pg_class c ON c.relnamespace = n.oid AND c.relname = s.tablename \
+         WHERE {} AND s.inherited = (c.relkind = 'p')",
+        pg_relation_filter(table)
+    );
+    let rows = sqlx::query(&sql)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("profile_columns error: {e}"))?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let mut by_name = std::collections::HashMap::new();
+    for r in &rows {
+        by_name.insert(r.try_get::<String, _>("name")?, r);
+    }
+
+    let rows_estimate = estimate.unwrap_or(0) as f64;
+    let mut profiles = Vec::with_capacity(columns.len());
+    for c in columns {
+        let Some(r) = by_name.get(&c.name) else {
+            profiles.push(json!({ "name": c.name, "data_type": c.data_type, "note": "no statistics" }));
+            continue;
+        };
+        // Negative n_distinct is a fraction of the row count.
+        let n_distinct: f64 = r.try_get("n_distinct")?;
+        let distinct = if n_distinct < 0.0 { -n_distinct * rows_estimate } else { n_distinct };
+
+        let values: Vec<String> = r
+            .try_get::<Option<String>, _>("common_values")?
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        let freqs: Vec<f64> = r
+            .try_get::<Option<String>, _>("common_freqs")?
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        let top: Vec<Value> = values
+            .into_iter()
+            .zip(freqs)
+            .take(top_k as usize)
+            .map(|(v, f)| {
+                let mut value = json!(v);
+                truncate_value(&mut value, 100);
+                json!({ "value": value, "frequency": f })
+            })
+            .collect();
+
+        profiles.push(json!({
+            "name": c.name,
+            "data_type": c.data_type,
+            "null_ratio": r.try_get::<f64, _>("null_frac")?,
+            "distinct_count": distinct.round() as i64,
+            "min": r.try_get::<Option<String>, _>("histogram_min")?,
+            "max": r.try_get::<Option<String>, _>("histogram_max")?,
+            "avg_width_bytes": r.try_get::<i32, _>("avg_width")?,
+            "top_values": top,
+        }));
+    }
+
+    Ok(Some(json!({
+        "table": table,
+        "source": "pg_stats",
+        "estimated_rows": estimate,
+        "columns": profiles,
+        "note": "Approximate values from planner statistics (last ANALYZE). min/max come from the histogram and exclude the most common values; use source 'full' for exact numbers."
+    })))
+}
+
+/// Finds the distinct values of low-cardinality columns (status, type,
+/// country, ...) so the model can write `WHERE` clauses with real values.
+/// Columns with more than `max_distinct` values are skipped. Results are
+/// cached alongside the connection's schema.
+pub async fn column_values(state: &SharedState, table: &str, column: Option<&str>, max_distinct: u32,) -> Result<Value> {
+    let cache_key = format!("{table}\u{0}{}\u{0}{max_distinct}", column.unwrap_or("*"));
+    let mut st = state.lock().await;
+    if let Some(cached) = st.schema_cache.values(&cache_key) {
+        return Ok(cached);
+    }
+    let pool = st.pool()?.clone();
+    let pool = &pool;
+    let kind = st.kind()?;
+
+    let mut columns = readable_columns(pool, kind, table).await?;
+    if let Some(name) = column {
+        columns.retain(|c| c.name == name);
+        if columns.is_empty() {
+            return Err(anyhow!("Column '{name}' not found in '{table}'."));
+        }
+    }
+    let estimate = estimated_rows(pool, kind, table).await?;
+    let sample = estimate.is_some_and(|n| n > PROFILE_FULL_SCAN_MAX_ROWS);
+    let from = qualified_table(kind, table);
+    let base = match (sample, kind, estimate) {
+        (true, DbKind::Postgres, Some(n)) => format!(
+            "{from} TABLESAMPLE SYSTEM ({:.6})",
+            PROFILE_SAMPLE_ROWS / n as f64 * 100.0
+        ),
+        (true, DbKind::MySQL, Some(n)) => format!(
+            "(SELECT * FROM {from} WHERE RAND() < {:.8}) AS sampled",
+            PROFILE_SAMPLE_ROWS / n as f64
+        ),
+        _ => from,
+    };
+    let planner_distinct = match kind {
+        DbKind::Postgres => pg_distinct_estimates(pool, table, estimate).await?,
+        DbKind::MySQL => Default::default(),
+    };
+
+    let mut found = serde_json::Map::new();
+    let mut skipped = Vec::new();
+    for c in &columns {
+        let category = categorize(kind, &c.base_type);
+        if matches!(category, Category::Binary | Category::Temporal) {
+            if column.is_some() {
+                skipped.push(json!({ "column": c.name, "reason": "binary or temporal column" }));
+            }
+            continue;
+        }
+        if let Some(n) = planner_distinct.get(&c.name).filter(|n| **n > max_distinct as f64) {
+            skipped.push(json!({ "column": c.name, "reason": format!("~{} distinct values", n.round()) }));
+            continue;
+        }
+
+        let q = quote_ident(kind, &c.name);
+        let value = match kind {
+            DbKind::MySQL => format!("CAST({q} AS CHAR)"),
+            DbKind::Postgres => format!("CAST({q} AS TEXT)"),
+        };
+        let sql = format!(
+            "SELECT {value} AS value, COUNT(*) AS count FROM {base} \
+             GROUP BY {value} ORDER BY COUNT(*) DESC, 1 LIMIT {}",
+            u64::from(max_distinct) + 1
+        );
+        let rows = sqlx::query(&sql)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| anyhow!("column_values error on '{}': {e}", c.name))?;
+        if rows.len() > max_distinct as usize {
+            skipped.push(json!({ "column": c.name, "reason": format!("more than {max_distinct} distinct values") }));
+            continue;
+        }
+        let values: Vec<Value> = rows.iter().map(row_to_json).collect();
+        found.insert(c.name.clone(), json!({
+            "data_type": c.data_type,
+            "distinct_count": values.len(),
+            "values": values
+        }));
+    }
+
+    let result = json!({
+        "table": table,
+        "max_distinct": max_distinct,
+        "sampled": sample,
+        "columns": found,
+        "skipped": skipped
+    });
+    st.schema_cache.put_values(cache_key, result.clone());
+    Ok(result)
+}
+
+/// Planner estimates of distinct values per column, used to skip obviously
+/// high-cardinality columns without scanning them.
+async fn pg_distinct_estimates(pool: &AnyPool, table: &str, estimate: Option<i64>,) -> Result<std::collections::HashMap<String, f64>> {
+    let sql = format!(
+        "SELECT s.attname::text AS name, s.n_distinct::float8 AS n_distinct \
+         FROM pg_catalog.pg_stats s \
+         JOIN pg_catalog.pg_namespace n ON n.nspname = s.schemaname \
+         JOIN pg_catalog.pg_class c ON c.relnamespace = n.oid AND c.relname = s.tablename \
+         WHERE {} AND s.inherited = (c.relkind = 'p')",
+        pg_relation_filter(table)
+    );
+    let rows = sqlx::query(&sql).fetch_all(pool).await?;
+    let total = estimate.unwrap_or(0) as f64;
+    let mut out = std::collections::HashMap::new();
+    for r in &rows {
+        let n: f64 = r.try_get("n_distinct")?;
+        out.insert(r.try_get("name")?, if n < 0.0 { -n * total } else { n });
+    }
+    Ok(out)
+}
@@ -0,0 +1,411 @@
+use crate::db::{decode_cell, describe_query, to_hex, Cell, DbKind, ReadableColumn, SharedState};
+use anyhow::{anyhow, Result};
+use futures_util::TryStreamExt;
+use parquet::basic::{Compression, LogicalType, Repetition, Type as PhysicalType};
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Rows buffered per Parquet row group.
+const PARQUET_ROW_GROUP: usize = 65_536;
+
+#[derive(Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Parses an explicit format, or infers it from the file extension.
+    pub fn resolve(format: Option<&str>, path: &str) -> Result<Self> {
+        let name = match format {
+            Some(f) => f.to_lowercase(),
+            None => Path::new(path)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_default(),
+        };
+        match name.as_str() {
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            "parquet" => Ok(Self::Parquet),
+            "" => Err(anyhow!("Cannot infer the format from '{path}'; pass format (csv, jsonl or parquet).")),
+            other => Err(anyhow!("Unknown format '{other}'. Use csv, jsonl or parquet.")),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Directories file tools may write into: `DB_MCP_EXPORT_DIRS` (a list in
+/// `PATH` syntax), or the working directory when unset.
+pub(crate) fn export_dirs() -> Vec<PathBuf> {
+    match std::env::var_os("DB_MCP_EXPORT_DIRS") {
+        Some(v) if !v.is_empty() => std::env::split_paths(&v).collect(),
+        _ => std::env::current_dir().into_iter().collect(),
+    }
+}
+
+/// Resolves `path` against the export directories (relative paths land in
+/// the first one) and refuses anything that ends up outside all of them.
+pub(crate) fn resolve_output(path: &str, overwrite: bool) -> Result<PathBuf> {
+    let dirs = export_dirs();
+    let first = dirs.first().ok_or_else(|| anyhow!("No export directory is configured."))?;
+    let requested = Path::new(path);
+    if requested.components().any(|c| c == Component::ParentDir) {
+        return Err(anyhow!("'..' is not allowed in export paths."));
+    }
+    let full = if requested.is_absolute() { requested.to_path_buf() } else { first.join(requested) };
+    let file_name = full.file_name().ok_or_else(|| anyhow!("'{path}' does not name a file."))?.to_owned();
+    let parent = full.parent().ok_or_else(|| anyhow!("'{path}' has no parent directory."))?;
+
+    let allowed = |dir: &Path| dirs.iter().any(|d| d.canonicalize().is_ok_and(|root| dir.starts_with(root)));
+    // Check before creating anything, then again once symlinks are resolved.
+    if !dirs.iter().any(|d| parent.starts_with(d)) {
+        return Err(anyhow!(
+            "'{}' is outside the export directories ({}). Set DB_MCP_EXPORT_DIRS to allow it.",
+            full.display(),
+            dirs.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    fs::create_dir_all(parent)?;
+    let parent = parent.canonicalize()?;
+    if !allowed(&parent) {
+        return Err(anyhow!("'{}' resolves outside the export directories.", full.display()));
+    }
+
+    let out = parent.join(file_name);
+    if out.exists() && !overwrite {
+        return Err(anyhow!("'{}' already exists; pass overwrite to replace it.", out.display()));
+    }
+    Ok(out)
+}
+
+/// Resolves a file to read against the same directories, after following
+/// symlinks, so imports cannot reach outside them either.
+pub(crate) fn resolve_input(path: &str) -> Result<PathBuf> {
+    let dirs = export_dirs();
+    let first = dirs.first().ok_or_else(|| anyhow!("No export directory is configured."))?;
+    let requested = Path::new(path);
+    let full = if requested.is_absolute() { requested.to_path_buf() } else { first.join(requested) };
+    let full = full.canonicalize().map_err(|e| anyhow!("Cannot open '{}': {e}", full.display()))?;
+    if !dirs.iter().any(|d| d.canonicalize().is_ok_and(|root| full.starts_with(root))) {
+        return Err(anyhow!(
+            "'{}' is outside the export directories. Set DB_MCP_EXPORT_DIRS to allow it.",
+            full.display()
+        ));
+    }
+    if !full.is_file() {
+        return Err(anyhow!("'{}' is not a file.", full.display()));
+    }
+    Ok(full)
+}
+
+/// Streams a query's rows to a CSV, JSONL or Parquet file and reports where
+/// it went, instead of returning the rows themselves.
+pub async fn export_query(
+    state: &SharedState,
+    sql: &str,
+    path: &str,
+    format: Option<&str>,
+    overwrite: bool,
+) -> Result<Value> {
+    let format = ExportFormat::resolve(format, path)?;
+    let out = resolve_output(path, overwrite)?;
+
+    let (pool, kind, url, timeout) = {
+        let st = state.lock().await;
+        (st.pool()?.clone(), st.kind()?, st.url.clone().unwrap_or_default(), st.pool_settings.acquire_timeout)
+    };
+    let sql = sql.trim().trim_end_matches(';');
+    let columns = describe_query(&url, timeout, kind, sql).await?;
+    let exprs: Vec<&str> = columns.iter().map(|c| c.expr.as_str()).collect();
+    let select = format!("SELECT {} FROM ({sql}) q", exprs.join(", "));
+
+    // Write next to the destination and rename, so a failed export never
+    // leaves a truncated file under the requested name.
+    let mut partial = out.clone().into_os_string();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let result = write_rows(&pool, &select, &columns, kind, format, &partial).await;
+    let rows = match result {
+        Ok(n) => n,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+    fs::rename(&partial, &out)?;
+
+    Ok(json!({
+        "path": out.display().to_string(),
+        "format": format.label(),
+        "rows": rows,
+        "bytes": fs::metadata(&out)?.len(),
+        "columns": columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>()
+    }))
+}
+
+async fn write_rows(
+    pool: &sqlx::AnyPool,
+    select: &str,
+    columns: &[ReadableColumn],
+    kind: DbKind,
+    format: ExportFormat,
+    path: &Path,
+) -> Result<u64> {
+    let file = File::create(path).map_err(|e| anyhow!("Cannot create '{}': {e}", path.display()))?;
+    let mut sink = match format {
+        ExportFormat::Csv => Sink::Csv(BufWriter::new(file)),
+        ExportFormat::Jsonl => Sink::Jsonl(BufWriter::new(file)),
+        ExportFormat::Parquet => Sink::Parquet(ParquetSink::new(file, columns, kind)?),
+    };
+    sink.header(columns)?;
+
+    let mut rows = 0u64;
+    let mut stream = sqlx::query(select).fetch(pool);
+    while let Some(row) = stream.try_next().await? {
+        let mut cells = Vec::with_capacity(columns.len());
+        for (i, c) in columns.iter().enumerate() {
+            cells.push(decode_cell(&row, i, c)?);
+        }
+        sink.row(columns, cells)?;
+        rows += 1;
+    }
+    sink.finish()?;
+    Ok(rows)
+}
+
+enum Sink {
+    Csv(BufWriter<File>),
+    Jsonl(BufWriter<File>),
+    Parquet(ParquetSink),
+}
+
+impl Sink {
+    fn header(&mut self, columns: &[ReadableColumn]) -> Result<()> {
+        if let Sink::Csv(w) = self {
+            let names: Vec<String> = columns.iter().map(|c| csv_field(&c.name)).collect();
+            writeln!(w, "{}", names.join(","))?;
+        }
+        Ok(())
+    }
+
+    fn row(&mut self, columns: &[ReadableColumn], cells: Vec<Cell>) -> Result<()> {
+        match self {
+            Sink::Csv(w) => {
+                let fields: Vec<String> = cells.iter().map(|c| csv_field(&cell_text(c))).collect();
+                writeln!(w, "{}", fields.join(","))?;
+            }
+            Sink::Jsonl(w) => {
+                // Built by hand to keep the query's column order.
+                let mut line = String::from("{");
+                for (i, (col, cell)) in columns.iter().zip(&cells).enumerate() {
+                    if i > 0 {
+                        line.push(',');
+                    }
+                    line.push_str(&serde_json::to_string(&col.name)?);
+                    line.push(':');
+                    line.push_str(&serde_json::to_string(&cell_json(cell))?);
+                }
+                line.push('}');
+                writeln!(w, "{line}")?;
+            }
+            Sink::Parquet(p) => p.row(cells)?,
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            Sink::Csv(mut w) | Sink::Jsonl(mut w) => w.flush()?,
+            Sink::Parquet(p) => p.finish()?,
+        }
+        Ok(())
+    }
+}
+
+/// Text form used by CSV: NULL is an empty field, bytes are `\x`-hex.
+fn cell_text(cell: &Cell) -> String {
+    match cell {
+        Cell::Null => String::new(),
+        Cell::Bool(b) => b.to_string(),
+        Cell::Int(n) => n.to_string(),
+        Cell::Float(f) => f.to_string(),
+        Cell::Text(s) => s.clone(),
+        Cell::Bytes(b) => format!("\\x{}", to_hex(b)),
+    }
+}
+
+fn cell_json(cell: &Cell) -> Value {
+    match cell {
+        Cell::Null => Value::Null,
+        Cell::Bool(b) => json!(b),
+        Cell::Int(n) => json!(n),
+        Cell::Float(f) => json!(f),
+        Cell::Text(s) => json!(s),
+        Cell::Bytes(b) => json!(format!("\\x{}", to_hex(b))),
+    }
+}
+
+/// RFC 4180 quoting, only where needed.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Column values for the current row group; `defs` holds 1 for a value and
+/// 0 for NULL, as Parquet's definition levels.
+struct ParquetColumn {
+    values: ParquetValues,
+    defs: Vec<i16>,
+}
+
+enum ParquetValues {
+    Bool(Vec<bool>),
+    Int(Vec<i64>),
+    Double(Vec<f64>),
+    Bytes(Vec<ByteArray>),
+}
+
+struct ParquetSink {
+    writer: SerializedFileWriter<File>,
+    columns: Vec<ParquetColumn>,
+    buffered: usize,
+}
+
+impl ParquetSink {
+    fn new(file: File, columns: &[ReadableColumn], kind: DbKind) -> Result<Self> {
+        let mut fields = Vec::with_capacity(columns.len());
+        let mut buffers = Vec::with_capacity(columns.len());
+        for c in columns {
+            let (physical, values) = parquet_type(kind, c);
+            let mut builder = Type::primitive_type_builder(&c.name, physical).with_repetition(Repetition::OPTIONAL);
+            if physical == PhysicalType::BYTE_ARRAY && !c.binary {
+                builder = builder.with_logical_type(Some(LogicalType::String));
+            }
+            fields.push(Arc::new(builder.build()?));
+            buffers.push(ParquetColumn { values, defs: Vec::new() });
+        }
+        let schema = Arc::new(Type::group_type_builder("schema").with_fields(fields).build()?);
+        let props = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+        Ok(Self { writer: SerializedFileWriter::new(file, schema, props)?, columns: buffers, buffered: 0 })
+    }
+
+    fn row(&mut self, cells: Vec<Cell>) -> Result<()> {
+        for (col, cell) in self.columns.iter_mut().zip(cells) {
+            let present = match (&mut col.values, cell) {
+                (_, Cell::Null) => false,
+                (ParquetValues::Bool(v), Cell::Bool(b)) => {
+                    v.push(b);
+                    true
+                }
+                (ParquetValues::Int(v), Cell::Int(n)) => {
+                    v.push(n);
+                    true
+                }
+                (ParquetValues::Double(v), Cell::Float(f)) => {
+                    v.push(f);
+                    true
+                }
+                (ParquetValues::Double(v), Cell::Int(n)) => {
+                    v.push(n as f64);
+                    true
+                }
+                (ParquetValues::Bytes(v), Cell::Bytes(b)) => {
+                    v.push(ByteArray::from(b));
+                    true
+                }
+                (ParquetValues::Bytes(v), other) => {
+                    v.push(ByteArray::from(cell_text(&other).into_bytes()));
+                    true
+                }
+                (_, other) => return Err(anyhow!("Unexpected value '{}' for a typed column", cell_text(&other))),
+            };
+            col.defs.push(i16::from(present));
+        }
+        self.buffered += 1;
+        if self.buffered >= PARQUET_ROW_GROUP {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.buffered == 0 {
+            return Ok(());
+        }
+        let mut group = self.writer.next_row_group()?;
+        for col in &mut self.columns {
+            let mut writer = group.next_column()?.ok_or_else(|| anyhow!("Parquet schema has fewer columns than the query"))?;
+            match &mut col.values {
+                ParquetValues::Bool(v) => {
+                    writer.typed::<BoolType>().write_batch(v, Some(&col.defs), None)?;
+                    v.clear();
+                }
+                ParquetValues::Int(v) => {
+                    writer.typed::<Int64Type>().write_batch(v, Some(&col.defs), None)?;
+                    v.clear();
+                }
+                ParquetValues::Double(v) => {
+                    writer.typed::<DoubleType>().write_batch(v, Some(&col.defs), None)?;
+                    v.clear();
+                }
+                ParquetValues::Bytes(v) => {
+                    writer.typed::<ByteArrayType>().write_batch(v, Some(&col.defs), None)?;
+                    v.clear();
+                }
+            }
+            col.defs.clear();
+            writer.close()?;
+        }
+        group.close()?;
+        self.buffered = 0;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.flush()?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+/// Maps a column to the Parquet type its decoded values come back as.
+fn parquet_type(kind: DbKind, c: &ReadableColumn) -> (PhysicalType, ParquetValues) {
+    let base = c.base_type.as_str();
+    let integer = match kind {
+        DbKind::Postgres => matches!(base, "int2" | "int4" | "int8"),
+        DbKind::MySQL => matches!(base, "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "year"),
+    };
+    let float = match kind {
+        DbKind::Postgres => matches!(base, "float4" | "float8"),
+        DbKind::MySQL => matches!(base, "float" | "double" | "real"),
+    };
+    if kind == DbKind::Postgres && base == "bool" {
+        (PhysicalType::BOOLEAN, ParquetValues::Bool(Vec::new()))
+    } else if integer {
+        (PhysicalType::INT64, ParquetValues::Int(Vec::new()))
+    } else if float {
+        (PhysicalType::DOUBLE, ParquetValues::Double(Vec::new()))
+    } else {
+        (PhysicalType::BYTE_ARRAY, ParquetValues::Bytes(Vec::new()))
+    }
+}
@@ -0,0 +1,554 @@
+use crate::db::{qualified_table, quote_ident, quote_literal, readable_columns, DbKind, SharedState};
+use crate::export::resolve_input;
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Records scanned to infer column types (and JSONL keys).
+const INFER_SAMPLE_ROWS: usize = 10_000;
+
+static DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap());
+static TIMESTAMP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}(:\d{2}(\.\d{1,6})?)?$").unwrap());
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    Csv,
+    Tsv,
+    Jsonl,
+}
+
+impl ImportFormat {
+    /// Parses an explicit format, or infers it from the file extension.
+    pub fn resolve(format: Option<&str>, path: &str) -> Result<Self> {
+        let name = match format {
+            Some(f) => f.to_lowercase(),
+            None => Path::new(path)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_default(),
+        };
+        match name.as_str() {
+            "csv" | "txt" => Ok(Self::Csv),
+            "tsv" | "tab" => Ok(Self::Tsv),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            "" => Err(anyhow!("Cannot infer the format from '{path}'; pass format (csv, tsv or jsonl).")),
+            other => Err(anyhow!("Unknown format '{other}'. Use csv, tsv or jsonl.")),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+pub struct ImportOptions {
+    pub format: Option<String>,
+    /// First CSV record holds column names; otherwise columns are `column_1`, ...
+    pub header: bool,
+    /// Overrides the format's field delimiter (CSV/TSV only).
+    pub delimiter: Option<char>,
+    /// File column → table column. A `None` target drops the file column.
+    pub mapping: HashMap<String, Option<String>>,
+    pub batch_size: usize,
+    /// Rejected rows listed in the result. The count is always complete.
+    pub max_rejected: usize,
+}
+
+/// Column type guessed from the file's values.
+#[derive(Clone, Copy, PartialEq)]
+enum Inferred {
+    /// Only NULLs seen so far.
+    Unknown,
+    Bool,
+    Int,
+    Float,
+    Date,
+    Timestamp,
+    Json,
+    Text,
+}
+
+impl Inferred {
+    fn of_text(v: &str) -> Self {
+        let digits = v.strip_prefix('-').unwrap_or(v);
+        if v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("false") {
+            Self::Bool
+        } else if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.") {
+            // Zip codes, phone numbers, IDs: keep the leading zeros.
+            Self::Text
+        } else if v.parse::<i64>().is_ok() {
+            Self::Int
+        } else if v.parse::<f64>().is_ok() && v.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b)) {
+            Self::Float
+        } else if DATE.is_match(v) {
+            Self::Date
+        } else if TIMESTAMP.is_match(v) {
+            Self::Timestamp
+        } else {
+            Self::Text
+        }
+    }
+
+    fn of_json(v: &Value) -> Self {
+        match v {
+            Value::Null => Self::Unknown,
+            Value::Bool(_) => Self::Bool,
+            Value::Number(n) if n.is_i64() => Self::Int,
+            Value::Number(_) => Self::Float,
+            Value::String(s) => Self::of_text(s),
+            Value::Array(_) | Value::Object(_) => Self::Json,
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        use Inferred::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Unknown, x) | (x, Unknown) => x,
+            (Int, Float) | (Float, Int) => Float,
+            (Date, Timestamp) | (Timestamp, Date) => Timestamp,
+            _ => Text,
+        }
+    }
+
+    fn sql_type(self, kind: DbKind) -> &'static str {
+        let pg = kind == DbKind::Postgres;
+        match self {
+            Self::Bool => if pg { "BOOLEAN" } else { "TINYINT(1)" },
+            Self::Int => "BIGINT",
+            Self::Float => if pg { "DOUBLE PRECISION" } else { "DOUBLE" },
+            Self::Date => "DATE",
+            Self::Timestamp => if pg { "TIMESTAMP" } else { "DATETIME(6)" },
+            Self::Json => if pg { "JSONB" } else { "JSON" },
+            Self::Unknown | Self::Text => if pg { "TEXT" } else { "LONGTEXT" },
+        }
+    }
+}
+
+/// One parsed record with the 1-based line it starts on.
+struct Record {
+    line: usize,
+    values: Vec<Option<String>>,
+}
+
+/// Reads either format as positional records. For JSONL the positions are
+/// the keys found in the sample, in first-seen order.
+enum Source {
+    Delimited(CsvReader),
+    Jsonl { lines: std::io::Lines<BufReader<File>>, line: usize, keys: Vec<String> },
+}
+
+impl Source {
+    /// Returns the next record, or `Err((line, reason))` for one that cannot
+    /// be parsed. Reading stops at `Ok(None)`.
+    fn next(&mut self, unknown_keys: &mut BTreeSet<String>) -> Result<Option<Record>, (usize, String)> {
+        match self {
+            Self::Delimited(reader) => reader.next_record(),
+            Self::Jsonl { lines, line, keys } => loop {
+                *line += 1;
+                let text = match lines.next() {
+                    None => return Ok(None),
+                    Some(Err(e)) => return Err((*line, e.to_string())),
+                    Some(Ok(t)) => t,
+                };
+                if text.trim().is_empty() {
+                    continue;
+                }
+                let obj = match serde_json::from_str::<Value>(&text) {
+                    Ok(Value::Object(o)) => o,
+                    Ok(_) => return Err((*line, "line is not a JSON object".into())),
+                    Err(e) => return Err((*line, format!("invalid JSON: {e}"))),
+                };
+                for k in obj.keys() {
+                    if !keys.contains(k) {
+                        unknown_keys.insert(k.clone());
+                    }
+                }
+                let values = keys.iter().map(|k| obj.get(k).and_then(json_text)).collect();
+                return Ok(Some(Record { line: *line, values }));
+            },
+        }
+    }
+}
+
+fn json_text(v: &Value) -> Option<String> {
+    match v {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// RFC 4180 reader: quoted fields may hold delimiters, doubled quotes and
+/// line breaks. An empty unquoted field is NULL; `""` is an empty string.
+struct CsvReader {
+    inner: BufReader<File>,
+    delimiter: char,
+    line: usize,
+}
+
+impl CsvReader {
+    fn next_record(&mut self) -> Result<Option<Record>, (usize, String)> {
+        let mut values = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let mut buf = String::new();
+        let mut start = self.line + 1;
+        loop {
+            buf.clear();
+            let n = self.inner.read_line(&mut buf).map_err(|e| (self.line + 1, e.to_string()))?;
+            if n == 0 {
+                if in_quotes {
+                    return Err((start, "unterminated quoted field".into()));
+                }
+                if self.line < start {
+                    return Ok(None);
+                }
+                break;
+            }
+            self.line += 1;
+            let text = if self.line == 1 { buf.trim_start_matches('\u{feff}') } else { buf.as_str() };
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c != '"' {
+                        field.push(c);
+                    } else if chars.peek() == Some(&'"') {
+                        field.push('"');
+                        chars.next();
+                    } else {
+                        in_quotes = false;
+                    }
+                } else if c == '"' && field.is_empty() && !quoted {
+                    in_quotes = true;
+                    quoted = true;
+                } else if c == self.delimiter {
+                    let f = std::mem::take(&mut field);
+                    values.push(if f.is_empty() && !quoted { None } else { Some(f) });
+                    quoted = false;
+                } else if c != '\n' && c != '\r' {
+                    field.push(c);
+                }
+            }
+            if !in_quotes {
+                if values.is_empty() && field.is_empty() && !quoted {
+                    // Blank line; the record starts on a later one.
+                    start = self.line + 1;
+                    continue;
+                }
+                break;
+            }
+        }
+        values.push(if field.is_empty() && !quoted { None } else { Some(field) });
+        Ok(Some(Record { line: start, values }))
+    }
+}
+
+/// Opens the file as a record source, reading the CSV header or collecting
+/// JSONL keys from the sample. Returns the source and the file's column names.
+fn open(path: &Path, format: ImportFormat, opts: &ImportOptions) -> Result<(Source, Vec<String>)> {
+    let reader = BufReader::new(File::open(path)?);
+    match format {
+        ImportFormat::Csv | ImportFormat::Tsv => {
+            let delimiter = opts.delimiter.unwrap_or(if format == ImportFormat::Tsv { '\t' } else { ',' });
+            let mut csv = CsvReader { inner: reader, delimiter, line: 0 };
+            let first = csv
+                .next_record()
+                .map_err(|(line, e)| anyhow!("Line {line}: {e}"))?
+                .ok_or_else(|| anyhow!("'{}' is empty.", path.display()))?;
+            let names = if opts.header {
+                first
+                    .values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, v)| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).unwrap_or(format!("column_{}", i + 1)))
+                    .collect()
+            } else {
+                let names = (1..=first.values.len()).map(|i| format!("column_{i}")).collect();
+                // Start over so the first record is imported too.
+                csv = CsvReader { inner: BufReader::new(File::open(path)?), delimiter, line: 0 };
+                names
+            };
+            Ok((Source::Delimited(csv), names))
+        }
+        ImportFormat::Jsonl => {
+            let mut keys: Vec<String> = Vec::new();
+            for line in reader.lines().take(INFER_SAMPLE_ROWS) {
+                if let Ok(Value::Object(obj)) = serde_json::from_str::<Value>(&line?) {
+                    for k in obj.keys() {
+                        if !keys.contains(k) {
+                            keys.push(k.clone());
+                        }
+                    }
+                }
+            }
+            if keys.is_empty() {
+                return Err(anyhow!("No JSON objects found in '{}'.", path.display()));
+            }
+            let lines = BufReader::new(File::open(path)?).lines();
+            Ok((Source::Jsonl { lines, line: 0, keys: keys.clone() }, keys))
+        }
+    }
+}
+
+/// Infers a type per file column from the first `INFER_SAMPLE_ROWS` records.
+fn infer_types(path: &Path, format: ImportFormat, opts: &ImportOptions, columns: &[String]) -> Result<Vec<Inferred>> {
+    let width = columns.len();
+    let mut types = vec![Inferred::Unknown; width];
+    let mut seen = 0;
+    if format == ImportFormat::Jsonl {
+        for line in BufReader::new(File::open(path)?).lines().take(INFER_SAMPLE_ROWS) {
+            if let Ok(Value::Object(obj)) = serde_json::from_str::<Value>(&line?) {
+                for (t, k) in types.iter_mut().zip(columns) {
+                    *t = t.merge(obj.get(k).map_or(Inferred::Unknown, Inferred::of_json));
+                }
+            }
+        }
+        return Ok(types);
+    }
+    let (mut source, _) = open(path, format, opts)?;
+    let mut ignored = BTreeSet::new();
+    while seen < INFER_SAMPLE_ROWS {
+        match source.next(&mut ignored) {
+            Ok(Some(rec)) if rec.values.len() == width => {
+                for (t, v) in types.iter_mut().zip(&rec.values) {
+                    *t = t.merge(v.as_deref().map_or(Inferred::Unknown, Inferred::of_text));
+                }
+                seen += 1;
+            }
+            Ok(Some(_)) | Err(_) => continue,
+            Ok(None) => break,
+        }
+    }
+    Ok(types)
+}
+
+/// Loads a CSV/TSV or JSONL file into `table`, creating it from inferred
+/// column types when it does not exist. Rows go in batches — `COPY` on
+/// PostgreSQL, multi-row `INSERT` on MySQL — and a batch the server refuses
+/// is retried row by row, so each bad row is rejected with the server's
+/// reason while the rest still load.
+pub async fn import_file(state: &SharedState, path: &str, table: &str, opts: &ImportOptions) -> Result<Value> {
+    let format = ImportFormat::resolve(opts.format.as_deref(), path)?;
+    let file = resolve_input(path)?;
+    let (pool, kind, url, timeout) = {
+        let st = state.lock().await;
+        st.writable()?;
+        (st.pool()?.clone(), st.kind()?, st.url.clone().unwrap_or_default(), st.pool_settings.acquire_timeout)
+    };
+
+    let (mut source, file_columns) = open(&file, format, opts)?;
+    for name in opts.mapping.keys() {
+        if !file_columns.contains(name) {
+            return Err(anyhow!("Mapped column '{name}' is not in the file (found: {}).", file_columns.join(", ")));
+        }
+    }
+    // (file position, table column) for every column that is kept.
+    let mapped: Vec<(usize, String)> = file_columns
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| match opts.mapping.get(name) {
+            Some(None) => None,
+            Some(Some(target)) => Some((i, target.clone())),
+            None => Some((i, name.clone())),
+        })
+        .collect();
+    let mut names: Vec<&str> = mapped.iter().map(|(_, n)| n.as_str()).collect();
+    names.sort_unstable();
+    if let Some(w) = names.windows(2).find(|w| w[0] == w[1]) {
+        return Err(anyhow!("More than one file column maps to '{}'.", w[0]));
+    }
+
+    let types = infer_types(&file, format, opts, &file_columns)?;
+    let mut created = false;
+    let mut skipped = Vec::new();
+    let (columns, bool_columns) = match readable_columns(&pool, kind, table).await {
+        Ok(existing) => {
+            let mut kept = Vec::new();
+            let mut bools = Vec::new();
+            for (i, name) in mapped {
+                match existing.iter().find(|c| c.name == name) {
+                    Some(c) => {
+                        bools.push(matches!(c.base_type.as_str(), "bool" | "boolean") || c.data_type.starts_with("tinyint(1)"));
+                        kept.push((i, name));
+                    }
+                    None => skipped.push(name),
+                }
+            }
+            (kept, bools)
+        }
+        Err(_) => {
+            let lines: Vec<String> = mapped
+                .iter()
+                .map(|(i, name)| format!("    {} {}", quote_ident(kind, name), types[*i].sql_type(kind)))
+                .collect();
+            let ddl = format!("CREATE TABLE {} (\n{}\n)", qualified_table(kind, table), lines.join(",\n"));
+            sqlx::query(&ddl)
+                .execute(&pool)
+                .await
+                .map_err(|e| anyhow!("Could not create '{table}': {e}\n{ddl}"))?;
+            created = true;
+            state.lock().await.schema_cache.invalidate();
+            let bools = mapped.iter().map(|(i, _)| types[*i] == Inferred::Bool).collect();
+            (mapped, bools)
+        }
+    };
+    if columns.is_empty() {
+        return Err(anyhow!("None of the file's columns exist in '{table}'."));
+    }
+
+    let quoted: Vec<String> = columns.iter().map(|(_, n)| quote_ident(kind, n)).collect();
+    let insert_head = format!("INSERT INTO {} ({}) VALUES ", qualified_table(kind, table), quoted.join(", "));
+    let copy_stmt = format!("COPY {} ({}) FROM STDIN WITH (FORMAT csv)", qualified_table(kind, table), quoted.join(", "));
+    let mut copy_conn = match kind {
+        DbKind::Postgres => Some(crate::db::connect_native::<sqlx::postgres::PgConnection>(&url, timeout).await?),
+        DbKind::MySQL => None,
+    };
+
+    let mut unknown_keys = BTreeSet::new();
+    let mut rejected = Vec::new();
+    let mut rejected_count = 0usize;
+    let mut reject = |line: usize, reason: String| {
+        rejected_count += 1;
+        if rejected.len() < opts.max_rejected {
+            rejected.push(json!({ "line": line, "reason": reason }));
+        }
+    };
+    // Cleared before the first write, so an import that fails part way does
+    // not leave column_values answering from the old rows.
+    state.lock().await.schema_cache.invalidate_values();
+    let (mut read, mut imported, mut batches) = (0usize, 0u64, 0usize);
+    let mut batch: Vec<Record> = Vec::with_capacity(opts.batch_size);
+    let width = file_columns.len();
+    loop {
+        let next = source.next(&mut unknown_keys);
+        let done = matches!(next, Ok(None));
+        match next {
+            Ok(Some(rec)) if format != ImportFormat::Jsonl && rec.values.len() != width => {
+                read += 1;
+                reject(rec.line, format!("expected {width} fields, found {}", rec.values.len()));
+            }
+            Ok(Some(rec)) => {
+                read += 1;
+                let values = columns
+                    .iter()
+                    .zip(&bool_columns)
+                    .map(|((i, _), is_bool)| {
+                        let v = rec.values[*i].clone();
+                        // MySQL booleans are TINYINT(1) and refuse 'true' in strict mode.
+                        match v {
+                            Some(s) if kind == DbKind::MySQL && *is_bool && s.eq_ignore_ascii_case("true") => Some("1".into()),
+                            Some(s) if kind == DbKind::MySQL && *is_bool && s.eq_ignore_ascii_case("false") => Some("0".into()),
+                            other => other,
+                        }
+                    })
+                    .collect();
+                batch.push(Record { line: rec.line, values });
+            }
+            Ok(None) => {}
+            Err((line, reason)) => {
+                read += 1;
+                reject(line, reason);
+            }
+        }
+        if batch.len() >= opts.batch_size || (done && !batch.is_empty()) {
+            batches += 1;
+            let whole = match copy_conn.as_mut() {
+                Some(conn) => copy_batch(conn, &copy_stmt, &batch).await,
+                None => {
+                    let rows: Vec<String> = batch.iter().map(|r| row_literal(kind, &r.values)).collect();
+                    let sql = format!("{insert_head}{}", rows.join(", "));
+                    sqlx::query(&sql).execute(&pool).await.map(|r| r.rows_affected()).map_err(anyhow::Error::from)
+                }
+            };
+            match whole {
+                Ok(_) => imported += batch.len() as u64,
+                Err(_) => {
+                    for rec in &batch {
+                        let sql = format!("{insert_head}{}", row_literal(kind, &rec.values));
+                        match sqlx::query(&sql).execute(&pool).await {
+                            Ok(_) => imported += 1,
+                            Err(e) => reject(rec.line, e.to_string()),
+                        }
+                    }
+                }
+            }
+            batch.clear();
+        }
+        if done {
+            break;
+        }
+    }
+
+    rejected.sort_by_key(|r| r["line"].as_u64());
+    let mut out = json!({
+        "path": file.display().to_string(),
+        "format": format.label(),
+        "table": table,
+        "created": created,
+        "method": if kind == DbKind::Postgres { "copy" } else { "insert" },
+        "columns": columns.iter().map(|(_, n)| n.as_str()).collect::<Vec<_>>(),
+        "columns_skipped": skipped,
+        "rows_read": read,
+        "rows_imported": imported,
+        "rows_rejected": rejected_count,
+        "batches": batches,
+        "rejected": rejected
+    });
+    if created {
+        out["column_types"] = columns
+            .iter()
+            .map(|(i, n)| (n.clone(), Value::from(types[*i].sql_type(kind))))
+            .collect::<serde_json::Map<_, _>>()
+            .into();
+    }
+    if !unknown_keys.is_empty() {
+        out["keys_ignored"] = json!(unknown_keys);
+    }
+    Ok(out)
+}
+
+/// Sends one batch through `COPY ... FROM STDIN` as CSV. All-or-nothing: any
+/// bad row fails the whole batch.
+async fn copy_batch(conn: &mut sqlx::postgres::PgConnection, stmt: &str, batch: &[Record]) -> Result<u64> {
+    let mut data = String::new();
+    for rec in batch {
+        let fields: Vec<String> = rec
+            .values
+            .iter()
+            .map(|v| match v {
+                None => String::new(),
+                Some(s) => format!("\"{}\"", s.replace('"', "\"\"")),
+            })
+            .collect();
+        data.push_str(&fields.join(","));
+        data.push('\n');
+    }
+    let mut copy = conn.copy_in_raw(stmt).await?;
+    if let Err(e) = copy.send(data.as_bytes()).await {
+        let _ = copy.abort("import batch failed").await;
+        return Err(e.into());
+    }
+    Ok(copy.finish().await?)
+}
+
+/// Values are quoted strings so the server coerces them to the column type.
+fn row_literal(kind: DbKind, values: &[Option<String>]) -> String {
+    let parts: Vec<String> = values
+        .iter()
+        .map(|v| v.as_deref().map_or("NULL".into(), |s| quote_literal(kind, s)))
+        .collect();
+    format!("({})", parts.join(", "))
+}
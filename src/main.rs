@@ -1,6 +1,19 @@
+mod copy;
+mod datadiff;
 mod db;
+mod ddl;
+mod diff;
+mod discover;
+mod explore;
+mod export;
+mod import;
+mod migrate;
+mod project;
 mod protocol;
+mod search;
+mod secrets;
 mod tools;
+mod tunnel;
 mod config;
 
 use anyhow::Result;
@@ -16,6 +29,10 @@ use tracing::{debug, error, info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Started by ssh as its SSH_ASKPASS program for a tunnel's key.
+    if let Ok(passphrase) = std::env::var(tunnel::ASKPASS_VAR) {
+        std::process::exit(tunnel::askpass(&passphrase));
+    }
     tracing_subscriber::fmt()
         .with_writer(std::io::stderr)
         .with_env_filter(
@@ -36,6 +53,8 @@ async fn main() -> Result<()> {
     let mut reader = BufReader::new(stdin);
     let mut writer = stdout;
     let mut line = String::new();
+    // Whether the client can report its workspace roots (`roots/list`).
+    let mut client_roots = false;
 
     loop {
         line.clear();
@@ -52,8 +71,21 @@ async fn main() -> Result<()> {
 
         debug!("← {trimmed}");
 
-        let request: JsonRpcRequest = match serde_json::from_str(trimmed) {
-            Ok(r) => r,
+        let request: JsonRpcRequest = match serde_json::from_str::<Value>(trimmed) {
+            // A reply to a request we sent the client.
+            Ok(v) if v.get("method").is_none() && v.get("id").is_some() => {
+                handle_client_response(&v);
+                continue;
+            }
+            Ok(v) => match serde_json::from_value(v) {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("Invalid JSON-RPC request: {e}");
+                    let resp = JsonRpcResponse::err(None, -32600, format!("Invalid request: {e}"));
+                    send(&mut writer, &resp).await?;
+                    continue;
+                }
+            },
             Err(e) => {
                 error!("Failed to parse JSON-RPC request: {e}");
                 let resp = JsonRpcResponse::err(None, -32700, format!("Parse error: {e}"));
@@ -64,8 +96,23 @@ async fn main() -> Result<()> {
 
         if request.id.is_none() {
             info!("Notification: {}", request.method);
+            let roots_changed = matches!(
+                request.method.as_str(),
+                "notifications/initialized" | "notifications/roots/list_changed"
+            );
+            if client_roots && roots_changed {
+                let ask = json!({ "jsonrpc": "2.0", "id": ROOTS_REQUEST_ID, "method": "roots/list" });
+                send(&mut writer, &ask).await?;
+            }
             continue;
         }
+        if request.method == "initialize" {
+            client_roots = request
+                .params
+                .as_ref()
+                .and_then(|p| p.pointer("/capabilities/roots"))
+                .is_some();
+        }
 
         let id = request.id.clone();
         let response = handle(&request, &state_holder).await;
@@ -128,7 +175,34 @@ async fn handle(
     }
 }
 
-async fn send<W: AsyncWriteExt + Unpin>(writer: &mut W, resp: &JsonRpcResponse,) -> Result<()> {
+/// Id of our `roots/list` request, to recognise the client's reply.
+const ROOTS_REQUEST_ID: &str = "db-mcp/roots";
+
+/// Handles the client's reply to a request we sent. The only one is
+/// `roots/list`, whose roots locate the project's connections file.
+fn handle_client_response(msg: &Value) {
+    if msg.get("id").and_then(|v| v.as_str()) != Some(ROOTS_REQUEST_ID) {
+        return;
+    }
+    if let Some(err) = msg.get("error") {
+        warn!("roots/list failed: {err}");
+        return;
+    }
+    let roots: Vec<std::path::PathBuf> = msg
+        .pointer("/result/roots")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|r| r.get("uri").and_then(|u| u.as_str()))
+        .filter_map(project::path_from_uri)
+        .collect();
+    match config::set_project_roots(&roots) {
+        Ok(n) => info!("Workspace roots: {roots:?}; {n} project connection(s)"),
+        Err(e) => warn!("Could not load project connections: {e}"),
+    }
+}
+
+async fn send<W: AsyncWriteExt + Unpin, T: serde::Serialize>(writer: &mut W, resp: &T) -> Result<()> {
     let mut json = serde_json::to_string(resp)?;
     json.push('\n');
     debug!("→ {}", json.trim());
@@ -0,0 +1,417 @@
+use crate::db::{qualified_table, quote_ident, quote_literal, split_table, DbKind};
+use crate::diff::{diff, dialect_name, ColumnDef, ConstraintDef, IndexDef, SchemaSnapshot, TableDef};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Serialize)]
+pub struct Step {
+    pub sql: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub destructive: bool,
+    /// Why a step is destructive or may fail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Statements that bring `target` in line with `source`, in execution order.
+struct Plan {
+    kind: DbKind,
+    steps: Vec<Step>,
+    warnings: Vec<String>,
+}
+
+/// Plans `up` (target → source) and `down` (source → target) migrations and
+/// optionally writes them as `<name>.up.sql` / `<name>.down.sql`.
+pub fn generate_migration(
+    src: &SchemaSnapshot,
+    tgt: &SchemaSnapshot,
+    source: &str,
+    target: &str,
+    output_dir: Option<&str>,
+    name: &str,
+) -> Result<Value> {
+    let kind = tgt.kind()?;
+    if src.kind()? != kind {
+        return Err(anyhow!(
+            "Cannot generate a migration from a {} schema to a {} schema; both sides must use the same database type.",
+            src.dialect,
+            tgt.dialect
+        ));
+    }
+    let up = plan(kind, src, tgt);
+    let down = plan(kind, tgt, src);
+
+    let mut files = Vec::new();
+    if let Some(dir) = output_dir {
+        for (suffix, p, from, to) in [("up", &up, target, source), ("down", &down, source, target)] {
+            // Confined to the export directories like any other file a tool writes.
+            let path = Path::new(dir).join(format!("{name}.{suffix}.sql"));
+            let path = crate::export::resolve_output(&path.to_string_lossy(), true)?;
+            let script = p.script(from, to);
+            fs::write(&path, &script).map_err(|e| anyhow!("Cannot write '{}': {e}", path.display()))?;
+            files.push(json!({ "path": path.display().to_string(), "bytes": script.len() }));
+        }
+    }
+
+    Ok(json!({
+        "source": source,
+        "target": target,
+        "dialect": dialect_name(kind),
+        "identical": up.steps.is_empty(),
+        "statement_count": up.steps.len(),
+        "destructive_count": up.steps.iter().filter(|s| s.destructive).count(),
+        "up": up.steps,
+        "down": down.steps,
+        "warnings": up.warnings,
+        "down_warnings": down.warnings,
+        "files": files
+    }))
+}
+
+fn plan(kind: DbKind, source: &SchemaSnapshot, target: &SchemaSnapshot) -> Plan {
+    let d = diff(source, target);
+    let mut p = Plan { kind, steps: Vec::new(), warnings: Vec::new() };
+    let is_fk = |c: &&ConstraintDef| c.kind == "foreign_key";
+    let not_fk = |c: &&ConstraintDef| c.kind != "foreign_key";
+
+    // Foreign keys go first so nothing below trips over a dependency,
+    // including those of tables about to be dropped.
+    for t in &d.tables_removed {
+        for c in target.tables[t].constraints.iter().filter(is_fk) {
+            p.drop_constraint(t, c);
+        }
+    }
+    for (t, td) in &d.tables_changed {
+        let gone = td.constraints_removed.iter().chain(td.constraints_changed.iter().map(|c| &c.target));
+        for c in gone.filter(is_fk) {
+            p.drop_constraint(t, c);
+        }
+    }
+
+    for (t, td) in &d.tables_changed {
+        for i in td.indexes_removed.iter().chain(td.indexes_changed.iter().map(|i| &i.target)) {
+            p.drop_index(t, i);
+        }
+        let gone = td.constraints_removed.iter().chain(td.constraints_changed.iter().map(|c| &c.target));
+        for c in gone.filter(not_fk) {
+            p.drop_constraint(t, c);
+        }
+    }
+
+    for t in &d.tables_added {
+        p.create_table(t, &source.tables[t]);
+    }
+
+    for (t, td) in &d.tables_changed {
+        for c in &td.columns_added {
+            p.add_column(t, c);
+        }
+        for c in &td.columns_changed {
+            p.alter_column(t, &c.source, &c.target, &c.fields);
+        }
+        for c in &td.columns_removed {
+            p.push(
+                format!("ALTER TABLE {} DROP COLUMN {}", qualified_table(kind, t), quote_ident(kind, &c.name)),
+                Some(format!("drops column {t}.{} and its data", c.name)),
+            );
+        }
+    }
+
+    for t in &d.tables_removed {
+        p.push(
+            format!("DROP TABLE {}", qualified_table(kind, t)),
+            Some(format!("drops table {t} and all of its rows")),
+        );
+    }
+
+    for t in &d.tables_added {
+        for i in &source.tables[t].indexes {
+            p.create_index(t, i);
+        }
+    }
+    for (t, td) in &d.tables_changed {
+        let new = td.constraints_added.iter().chain(td.constraints_changed.iter().map(|c| &c.source));
+        for c in new.filter(not_fk) {
+            p.add_constraint(t, c);
+        }
+        for i in td.indexes_added.iter().chain(td.indexes_changed.iter().map(|i| &i.source)) {
+            p.create_index(t, i);
+        }
+    }
+
+    for t in &d.tables_added {
+        for c in source.tables[t].constraints.iter().filter(is_fk) {
+            p.add_constraint(t, c);
+        }
+    }
+    for (t, td) in &d.tables_changed {
+        let new = td.constraints_added.iter().chain(td.constraints_changed.iter().map(|c| &c.source));
+        for c in new.filter(is_fk) {
+            p.add_constraint(t, c);
+        }
+    }
+
+    p
+}
+
+impl Plan {
+    /// Adds a statement; a `destructive` reason marks it as losing data.
+    fn push(&mut self, sql: String, destructive: Option<String>) {
+        self.steps.push(Step { sql, destructive: destructive.is_some(), note: destructive });
+    }
+
+    fn note(&mut self, sql: String, note: String) {
+        self.steps.push(Step { sql, destructive: false, note: Some(note) });
+    }
+
+    fn script(&self, from: &str, to: &str) -> String {
+        let mut out = format!("-- Migration generated by db-mcp\n-- from: {from}\n-- to:   {to}\n");
+        for w in &self.warnings {
+            out.push_str(&format!("-- WARNING: {w}\n"));
+        }
+        // PostgreSQL DDL is transactional; MySQL commits after every DDL.
+        let transactional = self.kind == DbKind::Postgres && !self.steps.is_empty();
+        if transactional {
+            out.push_str("\nBEGIN;\n");
+        }
+        for s in &self.steps {
+            out.push('\n');
+            match (&s.note, s.destructive) {
+                (Some(n), true) => out.push_str(&format!("-- DESTRUCTIVE: {n}\n")),
+                (Some(n), false) => out.push_str(&format!("-- NOTE: {n}\n")),
+                _ => {}
+            }
+            out.push_str(&s.sql);
+            out.push_str(";\n");
+        }
+        if transactional {
+            out.push_str("\nCOMMIT;\n");
+        }
+        out
+    }
+
+    fn create_table(&mut self, table: &str, def: &TableDef) {
+        let kind = self.kind;
+        let mut lines = Vec::new();
+        for c in &def.columns {
+            self.ensure_sequence(c);
+            lines.push(format!("    {}", self.column_sql(table, c)));
+        }
+        for c in def.constraints.iter().filter(|c| c.kind != "foreign_key") {
+            lines.push(format!("    {}", inline_constraint(kind, c)));
+        }
+        self.push(format!("CREATE TABLE {} (\n{}\n)", qualified_table(kind, table), lines.join(",\n")), None);
+    }
+
+    fn add_column(&mut self, table: &str, c: &ColumnDef) {
+        self.ensure_sequence(c);
+        let sql = format!("ALTER TABLE {} ADD COLUMN {}", qualified_table(self.kind, table), self.column_sql(table, c));
+        if !c.nullable && c.default.is_none() && c.extra.is_none() {
+            self.note(sql, format!("{table}.{} is NOT NULL without a default; fails if the table has rows", c.name));
+        } else {
+            self.push(sql, None);
+        }
+    }
+
+    fn alter_column(&mut self, table: &str, src: &ColumnDef, tgt: &ColumnDef, fields: &[&str]) {
+        let kind = self.kind;
+        let t = qualified_table(kind, table);
+        let col = quote_ident(kind, &src.name);
+        let type_change = fields.contains(&"data_type")
+            .then(|| format!("changes {table}.{} from {} to {}; values may be truncated or fail to convert", src.name, tgt.data_type, src.data_type));
+
+        if kind == DbKind::MySQL {
+            // MODIFY restates the whole column, covering every field at once.
+            let sql = format!("ALTER TABLE {t} MODIFY COLUMN {}", self.column_sql(table, src));
+            self.push(sql, type_change);
+            return;
+        }
+
+        if is_generated(src) || is_generated(tgt) {
+            self.warnings.push(format!(
+                "{table}.{} is a generated column whose definition changed; recreate it manually",
+                src.name
+            ));
+            return;
+        }
+        if let Some(note) = type_change {
+            self.push(
+                format!("ALTER TABLE {t} ALTER COLUMN {col} TYPE {ty} USING {col}::{ty}", ty = src.data_type),
+                Some(note),
+            );
+        }
+        if fields.contains(&"nullable") {
+            if src.nullable {
+                self.push(format!("ALTER TABLE {t} ALTER COLUMN {col} DROP NOT NULL"), None);
+            } else {
+                self.note(
+                    format!("ALTER TABLE {t} ALTER COLUMN {col} SET NOT NULL"),
+                    format!("fails if {table}.{} contains NULLs", src.name),
+                );
+            }
+        }
+        if fields.contains(&"default") {
+            match &src.default {
+                Some(d) => {
+                    self.ensure_sequence(src);
+                    self.push(format!("ALTER TABLE {t} ALTER COLUMN {col} SET DEFAULT {d}"), None);
+                }
+                None => self.push(format!("ALTER TABLE {t} ALTER COLUMN {col} DROP DEFAULT"), None),
+            }
+        }
+        if fields.contains(&"extra") {
+            let sql = match (src.extra.as_deref(), tgt.extra.as_deref()) {
+                (Some(identity), None) => format!("ALTER TABLE {t} ALTER COLUMN {col} ADD {identity}"),
+                (None, Some(_)) => format!("ALTER TABLE {t} ALTER COLUMN {col} DROP IDENTITY IF EXISTS"),
+                (Some(identity), Some(_)) => {
+                    let mode = identity.trim_start_matches("GENERATED ").trim_end_matches(" AS IDENTITY");
+                    format!("ALTER TABLE {t} ALTER COLUMN {col} SET GENERATED {mode}")
+                }
+                (None, None) => return,
+            };
+            self.push(sql, None);
+        }
+    }
+
+    fn create_index(&mut self, table: &str, i: &IndexDef) {
+        let kind = self.kind;
+        if let (DbKind::Postgres, Some(def)) = (kind, &i.definition) {
+            self.push(def.clone(), None);
+            return;
+        }
+        if i.columns.iter().any(|c| c == "(expression)") {
+            self.warnings.push(format!("index {table}.{} is on an expression; create it manually", i.name));
+            return;
+        }
+        let cols: Vec<String> = match kind {
+            DbKind::MySQL => i.columns.iter().map(|c| quote_ident(kind, c)).collect(),
+            // pg_get_indexdef already renders each key as SQL.
+            DbKind::Postgres => i.columns.clone(),
+        };
+        self.push(
+            format!(
+                "CREATE {}INDEX {} ON {} ({})",
+                if i.unique { "UNIQUE " } else { "" },
+                quote_ident(kind, &i.name),
+                qualified_table(kind, table),
+                cols.join(", ")
+            ),
+            None,
+        );
+    }
+
+    fn drop_index(&mut self, table: &str, i: &IndexDef) {
+        let kind = self.kind;
+        let sql = match kind {
+            DbKind::MySQL => format!("DROP INDEX {} ON {}", quote_ident(kind, &i.name), qualified_table(kind, table)),
+            DbKind::Postgres => match split_table(table) {
+                (Some(schema), _) => {
+                    format!("DROP INDEX {}.{}", quote_ident(kind, schema), quote_ident(kind, &i.name))
+                }
+                (None, _) => format!("DROP INDEX {}", quote_ident(kind, &i.name)),
+            },
+        };
+        self.push(sql, None);
+    }
+
+    fn add_constraint(&mut self, table: &str, c: &ConstraintDef) {
+        let sql = format!("ALTER TABLE {} ADD {}", qualified_table(self.kind, table), inline_constraint(self.kind, c));
+        self.push(sql, None);
+    }
+
+    fn drop_constraint(&mut self, table: &str, c: &ConstraintDef) {
+        let kind = self.kind;
+        let name = quote_ident(kind, &c.name);
+        let action = match (kind, c.kind.as_str()) {
+            (DbKind::MySQL, "primary_key") => "DROP PRIMARY KEY".to_string(),
+            (DbKind::MySQL, "foreign_key") => format!("DROP FOREIGN KEY {name}"),
+            (DbKind::MySQL, "check") => format!("DROP CHECK {name}"),
+            (DbKind::MySQL, _) => format!("DROP INDEX {name}"),
+            (DbKind::Postgres, _) => format!("DROP CONSTRAINT {name}"),
+        };
+        self.push(format!("ALTER TABLE {} {action}", qualified_table(kind, table)), None);
+    }
+
+    fn column_sql(&mut self, table: &str, c: &ColumnDef) -> String {
+        let kind = self.kind;
+        let mut sql = format!("{} {}", quote_ident(kind, &c.name), c.data_type);
+        match kind {
+            DbKind::Postgres => {
+                match (&c.default, is_generated(c)) {
+                    (Some(expr), true) => sql.push_str(&format!(" GENERATED ALWAYS AS ({expr}) STORED")),
+                    (Some(d), false) => sql.push_str(&format!(" DEFAULT {d}")),
+                    (None, _) => {}
+                }
+                if !c.nullable {
+                    sql.push_str(" NOT NULL");
+                }
+                if let Some(identity) = c.extra.as_deref().filter(|_| !is_generated(c)) {
+                    sql.push_str(&format!(" {identity}"));
+                }
+            }
+            DbKind::MySQL => {
+                let extra = c.extra.as_deref().unwrap_or("");
+                sql.push_str(if c.nullable { " NULL" } else { " NOT NULL" });
+                if let Some(d) = &c.default {
+                    sql.push_str(&format!(" DEFAULT {}", mysql_default(d, extra)));
+                }
+                let lower = extra.to_lowercase();
+                if lower.contains("auto_increment") {
+                    sql.push_str(" AUTO_INCREMENT");
+                }
+                if let Some(pos) = lower.find("on update ") {
+                    sql.push_str(&format!(" ON UPDATE {}", &extra[pos + "on update ".len()..]));
+                }
+                if lower.contains("virtual generated") || lower.contains("stored generated") {
+                    self.warnings.push(format!(
+                        "{table}.{} is a generated column; its expression is not captured, add it manually",
+                        c.name
+                    ));
+                }
+            }
+        }
+        sql
+    }
+
+    /// Serial-style defaults need their sequence to exist on the target.
+    fn ensure_sequence(&mut self, c: &ColumnDef) {
+        let Some(rest) = c.default.as_deref().and_then(|d| d.split_once("nextval('")).map(|(_, r)| r) else {
+            return;
+        };
+        if let Some((seq, _)) = rest.split_once('\'') {
+            self.push(format!("CREATE SEQUENCE IF NOT EXISTS {seq}"), None);
+        }
+    }
+}
+
+fn is_generated(c: &ColumnDef) -> bool {
+    c.extra.as_deref() == Some("GENERATED ALWAYS (STORED)")
+}
+
+fn inline_constraint(kind: DbKind, c: &ConstraintDef) -> String {
+    if kind == DbKind::MySQL && c.kind == "primary_key" {
+        return c.definition.clone();
+    }
+    format!("CONSTRAINT {} {}", quote_ident(kind, &c.name), c.definition)
+}
+
+/// `information_schema.columns.column_default` is unquoted on MySQL, so
+/// literals have to be told apart from expressions.
+fn mysql_default(d: &str, extra: &str) -> String {
+    let upper = d.to_uppercase();
+    let is_now = upper.starts_with("CURRENT_TIMESTAMP") || upper.starts_with("NOW(");
+    if extra.contains("DEFAULT_GENERATED") {
+        if is_now {
+            d.to_string()
+        } else {
+            format!("({d})")
+        }
+    } else if is_now || upper == "NULL" || d.parse::<f64>().is_ok() || d.starts_with('\'') || d.starts_with("b'") {
+        d.to_string()
+    } else {
+        quote_literal(DbKind::MySQL, d)
+    }
+}
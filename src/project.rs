@@ -0,0 +1,124 @@
+use crate::config::{default_port, home_dir, Entry};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Location of the project file relative to a project root.
+const PROJECT_FILE: &str = ".db-mcp/connections.toml";
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProjectFile {
+    /// Default policy for every connection in the file.
+    #[serde(default)]
+    read_only: bool,
+    #[serde(default)]
+    connections: BTreeMap<String, ProjectConnection>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProjectConnection {
+    dbtype: String,
+    #[serde(alias = "ip")]
+    host: String,
+    port: Option<u16>,
+    #[serde(default, alias = "user")]
+    username: String,
+    #[serde(default)]
+    password: String,
+    #[serde(default)]
+    database: String,
+    read_only: Option<bool>,
+    /// Numbers and booleans are allowed too, e.g. `max_connections = 2`.
+    #[serde(default)]
+    options: BTreeMap<String, toml::Value>,
+    ssh: Option<crate::tunnel::SshTunnel>,
+}
+
+/// A connection defined by a project's `connections.toml`.
+pub struct ProjectEntry {
+    pub entry: Entry,
+    pub read_only: bool,
+    pub file: PathBuf,
+}
+
+/// Project files for the given roots, or — when the client reported none —
+/// the nearest one above the working directory. The home directory is not
+/// searched: its `.db-mcp/` holds the global config.
+pub fn project_files(roots: &[PathBuf]) -> Vec<PathBuf> {
+    if !roots.is_empty() {
+        return roots.iter().map(|r| r.join(PROJECT_FILE)).filter(|f| f.is_file()).collect();
+    }
+    let home = home_dir();
+    let Ok(cwd) = std::env::current_dir() else { return Vec::new() };
+    cwd.ancestors()
+        .take_while(|dir| home.as_deref() != Some(*dir))
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|f| f.is_file())
+        .into_iter()
+        .collect()
+}
+
+/// Reads the connections of each project file. When two files define the
+/// same name, the first one wins.
+pub fn load(files: &[PathBuf]) -> Result<Vec<ProjectEntry>, String> {
+    let mut entries: Vec<ProjectEntry> = Vec::new();
+    for file in files {
+        for e in load_file(file)? {
+            if !entries.iter().any(|x| x.entry.name() == e.entry.name()) {
+                entries.push(e);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn load_file(file: &Path) -> Result<Vec<ProjectEntry>, String> {
+    let text = std::fs::read_to_string(file).map_err(|e| format!("Cannot read '{}': {e}", file.display()))?;
+    let parsed: ProjectFile = toml::from_str(&text).map_err(|e| format!("Invalid '{}': {e}", file.display()))?;
+    parsed
+        .connections
+        .into_iter()
+        .map(|(name, c)| {
+            let Some(port) = c.port.or(default_port(&c.dbtype)) else {
+                return Err(format!("'{}': connection '{name}' has unknown dbtype '{}'.", file.display(), c.dbtype));
+            };
+            let options = c
+                .options
+                .into_iter()
+                .map(|(k, v)| match v {
+                    toml::Value::String(s) => Ok((k, s)),
+                    toml::Value::Integer(_) | toml::Value::Boolean(_) => Ok((k, v.to_string())),
+                    _ => Err(format!("'{}': connection '{name}': option '{k}' must be a string, number or boolean.", file.display())),
+                })
+                .collect::<Result<_, _>>()?;
+            let passphrase = c.ssh.as_ref().and_then(|ssh| ssh.passphrase.as_deref());
+            crate::secrets::check_project_value("password", &c.password)
+                .and_then(|_| passphrase.map_or(Ok(()), |p| crate::secrets::check_project_value("ssh.passphrase", p)))
+                .map_err(|e| format!("'{}': connection '{name}': {e}", file.display()))?;
+            let entry = Entry::new(name.clone(), c.host, port, c.username, c.password, c.dbtype, c.database)
+                .with_ssh(c.ssh)
+                .with_options(options)
+                .map_err(|e| format!("'{}': connection '{name}': {e}", file.display()))?;
+            Ok(ProjectEntry {
+                entry,
+                read_only: c.read_only.unwrap_or(parsed.read_only),
+                file: file.to_path_buf(),
+            })
+        })
+        .collect()
+}
+
+/// Turns a `file://` root URI from the client into a path.
+pub fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    // `file:///path` or `file://localhost/path`.
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    let path = crate::config::percent_decode(path)?;
+    // Windows roots arrive as `file:///C:/...`.
+    let path = match path.as_bytes() {
+        [b'/', _, b':', ..] => &path[1..],
+        _ => &path[..],
+    };
+    Some(PathBuf::from(path))
+}
@@ -0,0 +1,252 @@
+use crate::db::{DbKind, SharedState};
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Map, Value};
+use sqlx::Row;
+
+/// Which object kinds `search_schema` looks at.
+const KINDS: [&str; 4] = ["table", "view", "column", "routine"];
+
+pub struct SearchOptions<'a> {
+    pub pattern: &'a str,
+    /// `substring` (default), `glob` or `regex`. Matching is case-insensitive.
+    pub mode: &'a str,
+    /// Substring the column's data type must contain, e.g. `timestamp`.
+    pub data_type: Option<&'a str>,
+    pub kinds: Vec<String>,
+    pub limit: usize,
+}
+
+/// Finds tables, views, columns and routines whose name or comment matches
+/// the pattern, without dumping the whole schema into the model's context.
+pub async fn search_schema(state: &SharedState, opts: &SearchOptions<'_>) -> Result<Value> {
+    let matcher = build_matcher(opts.pattern, opts.mode)?;
+    let data_type = opts.data_type.map(|t| t.to_lowercase());
+    let wants = |k: &str| opts.kinds.is_empty() || opts.kinds.iter().any(|w| w == k);
+
+    let st = state.lock().await;
+    let pool = st.pool()?;
+    let kind = st.kind()?;
+
+    let mut matches = Map::new();
+    let mut total = 0usize;
+    let mut truncated = false;
+    let mut push = |bucket: &str, entry: Value, matches: &mut Map<String, Value>| {
+        if total >= opts.limit {
+            truncated = true;
+            return;
+        }
+        total += 1;
+        if let Value::Array(list) = matches.entry(bucket).or_insert_with(|| json!([])) {
+            list.push(entry);
+        }
+    };
+
+    // A data-type filter only makes sense for columns.
+    let relations_wanted = data_type.is_none() && (wants("table") || wants("view"));
+    if relations_wanted {
+        for row in sqlx::query(relations_sql(kind)).fetch_all(pool).await? {
+            let object_kind: String = row.try_get("kind")?;
+            if !wants(&object_kind) {
+                continue;
+            }
+            let name: String = row.try_get("name")?;
+            let comment: Option<String> = row.try_get("comment")?;
+            if let Some(on) = matched_on(&matcher, &name, comment.as_deref()) {
+                let entry = json!({
+                    "schema": row.try_get::<String, _>("schema_name")?,
+                    "name": name,
+                    "comment": non_empty(comment),
+                    "matched_on": on
+                });
+                push(&format!("{object_kind}s"), entry, &mut matches);
+            }
+        }
+    }
+
+    if wants("column") || data_type.is_some() {
+        for row in sqlx::query(columns_sql(kind)).fetch_all(pool).await? {
+            let column_type: String = row.try_get("data_type")?;
+            if let Some(t) = &data_type {
+                if !column_type.to_lowercase().contains(t.as_str()) {
+                    continue;
+                }
+            }
+            let name: String = row.try_get("name")?;
+            let comment: Option<String> = row.try_get("comment")?;
+            if let Some(on) = matched_on(&matcher, &name, comment.as_deref()) {
+                let entry = json!({
+                    "schema": row.try_get::<String, _>("schema_name")?,
+                    "table": row.try_get::<String, _>("table_name")?,
+                    "name": name,
+                    "data_type": column_type,
+                    "comment": non_empty(comment),
+                    "matched_on": on
+                });
+                push("columns", entry, &mut matches);
+            }
+        }
+    }
+
+    if data_type.is_none() && wants("routine") {
+        for row in sqlx::query(routines_sql(kind)).fetch_all(pool).await? {
+            let name: String = row.try_get("name")?;
+            let comment: Option<String> = row.try_get("comment")?;
+            if let Some(on) = matched_on(&matcher, &name, comment.as_deref()) {
+                let entry = json!({
+                    "schema": row.try_get::<String, _>("schema_name")?,
+                    "name": name,
+                    "kind": row.try_get::<String, _>("kind")?,
+                    "arguments": row.try_get::<Option<String>, _>("arguments")?,
+                    "returns": row.try_get::<Option<String>, _>("returns")?,
+                    "comment": non_empty(comment),
+                    "matched_on": on
+                });
+                push("routines", entry, &mut matches);
+            }
+        }
+    }
+
+    Ok(json!({
+        "pattern": opts.pattern,
+        "mode": opts.mode,
+        "matches": matches,
+        "match_count": total,
+        "truncated": truncated
+    }))
+}
+
+/// Validates a comma-separated `kinds` argument.
+pub fn parse_kinds(raw: &str) -> Result<Vec<String>> {
+    raw.split(',')
+        .map(|k| k.trim().trim_end_matches('s').to_lowercase())
+        .filter(|k| !k.is_empty())
+        .map(|k| {
+            if KINDS.contains(&k.as_str()) {
+                Ok(k)
+            } else {
+                Err(anyhow!("Unknown kind '{k}'. Use any of: {}.", KINDS.join(", ")))
+            }
+        })
+        .collect()
+}
+
+fn build_matcher(pattern: &str, mode: &str) -> Result<Regex> {
+    let source = match mode {
+        "substring" => regex::escape(pattern),
+        "glob" => {
+            let mut re = String::from("^");
+            for c in pattern.chars() {
+                match c {
+                    '*' => re.push_str(".*"),
+                    '?' => re.push('.'),
+                    other => re.push_str(&regex::escape(&other.to_string())),
+                }
+            }
+            re.push('$');
+            re
+        }
+        "regex" => pattern.to_string(),
+        other => return Err(anyhow!("Unknown mode '{other}'. Use substring, glob or regex.")),
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| anyhow!("Invalid pattern: {e}"))
+}
+
+fn matched_on(matcher: &Regex, name: &str, comment: Option<&str>) -> Option<&'static str> {
+    if matcher.is_match(name) {
+        Some("name")
+    } else if comment.is_some_and(|c| matcher.is_match(c)) {
+        Some("comment")
+    } else {
+        None
+    }
+}
+
+fn non_empty(s: Option<String>) -> Option<String> {
+    s.filter(|s| !s.is_empty())
+}
+
+fn relations_sql(kind: DbKind) -> &'static str {
+    match kind {
+        DbKind::MySQL => {
+            "SELECT CAST(table_schema AS CHAR) AS schema_name, \
+             CAST(table_name AS CHAR) AS name, \
+             CASE WHEN table_type = 'VIEW' THEN 'view' ELSE 'table' END AS kind, \
+             CAST(table_comment AS CHAR) AS comment \
+             FROM information_schema.tables \
+             WHERE table_schema = DATABASE() \
+             ORDER BY table_name"
+        }
+        DbKind::Postgres => {
+            "SELECT n.nspname::text AS schema_name, c.relname::text AS name, \
+             CASE WHEN c.relkind IN ('v', 'm') THEN 'view' ELSE 'table' END AS kind, \
+             pg_catalog.obj_description(c.oid, 'pg_class') AS comment \
+             FROM pg_catalog.pg_class c \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f') \
+               AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+               AND n.nspname NOT LIKE 'pg_toast%' \
+             ORDER BY n.nspname, c.relname"
+        }
+    }
+}
+
+fn columns_sql(kind: DbKind) -> &'static str {
+    match kind {
+        DbKind::MySQL => {
+            "SELECT CAST(table_schema AS CHAR) AS schema_name, \
+             CAST(table_name AS CHAR) AS table_name, \
+             CAST(column_name AS CHAR) AS name, \
+             CAST(column_type AS CHAR) AS data_type, \
+             CAST(column_comment AS CHAR) AS comment \
+             FROM information_schema.columns \
+             WHERE table_schema = DATABASE() \
+             ORDER BY table_name, ordinal_position"
+        }
+        DbKind::Postgres => {
+            "SELECT n.nspname::text AS schema_name, c.relname::text AS table_name, \
+             a.attname::text AS name, \
+             pg_catalog.format_type(a.atttypid, a.atttypmod) AS data_type, \
+             pg_catalog.col_description(c.oid, a.attnum) AS comment \
+             FROM pg_catalog.pg_attribute a \
+             JOIN pg_catalog.pg_class c ON c.oid = a.attrelid \
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+             WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f') \
+               AND a.attnum > 0 AND NOT a.attisdropped \
+               AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+               AND n.nspname NOT LIKE 'pg_toast%' \
+             ORDER BY n.nspname, c.relname, a.attnum"
+        }
+    }
+}
+
+fn routines_sql(kind: DbKind) -> &'static str {
+    match kind {
+        DbKind::MySQL => {
+            "SELECT CAST(routine_schema AS CHAR) AS schema_name, \
+             CAST(routine_name AS CHAR) AS name, \
+             LOWER(CAST(routine_type AS CHAR)) AS kind, \
+             CAST(NULL AS CHAR) AS arguments, \
+             CAST(dtd_identifier AS CHAR) AS returns, \
+             CAST(routine_comment AS CHAR) AS comment \
+             FROM information_schema.routines \
+             WHERE routine_schema = DATABASE() \
+             ORDER BY routine_name"
+        }
+        DbKind::Postgres => {
+            "SELECT n.nspname::text AS schema_name, p.proname::text AS name, \
+             CASE p.prokind WHEN 'p' THEN 'procedure' WHEN 'a' THEN 'aggregate' \
+                            WHEN 'w' THEN 'window' ELSE 'function' END AS kind, \
+             pg_catalog.pg_get_function_identity_arguments(p.oid) AS arguments, \
+             pg_catalog.pg_get_function_result(p.oid) AS returns, \
+             pg_catalog.obj_description(p.oid, 'pg_proc') AS comment \
+             FROM pg_catalog.pg_proc p \
+             JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace \
+             WHERE n.nspname NOT IN ('pg_catalog', 'information_schema') \
+             ORDER BY n.nspname, p.proname"
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const PREFIX: &str = "enc:v1:";
+
+/// Keys derived so far, by salt. Argon2 is deliberately slow, and every value
+/// written by one process shares that process's salt.
+static KEYS: OnceLock<Mutex<HashMap<[u8; 16], [u8; 32]>>> = OnceLock::new();
+static WRITE_SALT: OnceLock<[u8; 16]> = OnceLock::new();
+
+/// How long a `cmd:` password source may run before it is killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Set to `1` to let tools save `file:` and `cmd:` sources.
+const ALLOW_TOOL_SOURCES_VAR: &str = "DB_MCP_ALLOW_CMD_SECRETS";
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+/// `env:`, `file:` and `cmd:` values name where the password comes from
+/// rather than holding it; they are stored as written.
+pub fn is_reference(value: &str) -> bool {
+    ["env:", "file:", "cmd:"].iter().any(|p| value.starts_with(p))
+}
+
+/// Refuses a `file:` or `cmd:` source passed as a tool argument: whoever
+/// drives the tools (or text injected into query results) could otherwise
+/// run a command, or send a local file as the password to a host of their
+/// choosing. Such sources belong in the hand-edited `~/.db-mcp/config.json`,
+/// unless `DB_MCP_ALLOW_CMD_SECRETS=1` allows them here too.
+pub fn check_tool_value(field: &str, value: &str) -> Result<(), String> {
+    match untrusted_source(value) {
+        None => Ok(()),
+        Some(kind) => Err(format!(
+            "'{field}' cannot be a {kind} source when passed to a tool. Write it into ~/.db-mcp/config.json \
+             by hand, or set {ALLOW_TOOL_SOURCES_VAR}=1 to allow it."
+        )),
+    }
+}
+
+/// The same check for a project's `connections.toml`, which arrives with
+/// whatever repository was cloned rather than being written by the user.
+pub fn check_project_value(field: &str, value: &str) -> Result<(), String> {
+    match untrusted_source(value) {
+        None => Ok(()),
+        Some(kind) => Err(format!(
+            "'{field}' cannot be a {kind} source in a project file; use env: there, \
+             or set {ALLOW_TOOL_SOURCES_VAR}=1 to trust the project."
+        )),
+    }
+}
+
+/// The `file:` or `cmd:` prefix of a value, unless such sources are allowed
+/// everywhere.
+fn untrusted_source(value: &str) -> Option<&'static str> {
+    let kind = ["file:", "cmd:"].into_iter().find(|p| value.starts_with(p))?;
+    (std::env::var(ALLOW_TOOL_SOURCES_VAR).map_or(true, |v| v != "1")).then_some(kind)
+}
+
+/// Produces the actual password for a saved value: reads the variable, file
+/// or command output a reference points at, decrypts an `enc:v1:` value, and
+/// returns anything else unchanged. Errors never include the password.
+pub fn resolve(value: &str) -> Result<String, String> {
+    if let Some(var) = value.strip_prefix("env:") {
+        return std::env::var(var).map_err(|_| format!("Environment variable '{var}' is not set."));
+    }
+    if let Some(path) = value.strip_prefix("file:") {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read password file '{path}': {e}"))?;
+        return Ok(text.trim_end_matches(['\r', '\n']).to_string());
+    }
+    if let Some(command) = value.strip_prefix("cmd:") {
+        return run_command(command);
+    }
+    decrypt(value)
+}
+
+/// Runs a password command through the shell and returns its first line of
+/// output. Stdin is closed so a command cannot stall on a prompt; stdout and
+/// stderr are drained on their own threads so a chatty command cannot fill
+/// a pipe and block.
+fn run_command(command: &str) -> Result<String, String> {
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot run password command: {e}"))?;
+
+    let (mut stdout, mut stderr) = (child.stdout.take(), child.stderr.take());
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let errors = std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(pipe) = stderr.as_mut() {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        });
+        let mut out = Vec::new();
+        if let Some(pipe) = stdout.as_mut() {
+            let _ = pipe.read_to_end(&mut out);
+        }
+        let _ = tx.send((out, errors.join().unwrap_or_default()));
+    });
+    // Both pipes close when the command exits.
+    let (stdout, stderr) = match rx.recv_timeout(COMMAND_TIMEOUT) {
+        Ok(output) => output,
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Password command timed out after {}s.", COMMAND_TIMEOUT.as_secs()));
+        }
+    };
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let reason = stderr.lines().next().unwrap_or("").trim();
+        let reason = if reason.is_empty() { String::new() } else { format!(": {reason}") };
+        return Err(format!("Password command failed ({status}){reason}"));
+    }
+    let stdout = String::from_utf8(stdout).map_err(|_| "Password command printed invalid UTF-8.".to_string())?;
+    let password = stdout.lines().next().unwrap_or("").to_string();
+    if password.is_empty() {
+        return Err("Password command printed nothing.".into());
+    }
+    Ok(password)
+}
+
+/// Where the key comes from, for messages; `None` when no key is configured.
+pub fn key_source() -> Option<&'static str> {
+    if std::env::var_os("DB_MCP_KEY_FILE").is_some_and(|v| !v.is_empty()) {
+        Some("DB_MCP_KEY_FILE")
+    } else if std::env::var_os("DB_MCP_PASSPHRASE").is_some_and(|v| !v.is_empty()) {
+        Some("DB_MCP_PASSPHRASE")
+    } else {
+        None
+    }
+}
+
+/// Key material: the contents of `DB_MCP_KEY_FILE`, else `DB_MCP_PASSPHRASE`.
+/// The cipher key is derived from it with Argon2id.
+fn secret() -> Result<Vec<u8>, String> {
+    match key_source() {
+        Some("DB_MCP_KEY_FILE") => {
+            let path = std::env::var_os("DB_MCP_KEY_FILE").unwrap_or_default();
+            let mut bytes = std::fs::read(&path)
+                .map_err(|e| format!("Cannot read key file '{}': {e}", std::path::Path::new(&path).display()))?;
+            while bytes.last().is_some_and(|b| b.is_ascii_whitespace()) {
+                bytes.pop();
+            }
+            if bytes.is_empty() {
+                return Err("The key file is empty.".into());
+            }
+            Ok(bytes)
+        }
+        Some(_) => Ok(std::env::var("DB_MCP_PASSPHRASE").unwrap_or_default().into_bytes()),
+        None => Err("No encryption key configured. Set DB_MCP_PASSPHRASE or DB_MCP_KEY_FILE.".into()),
+    }
+}
+
+fn derive(salt: &[u8; 16]) -> Result<ChaCha20Poly1305, String> {
+    let keys = KEYS.get_or_init(Default::default);
+    if let Some(key) = keys.lock().map_err(|e| e.to_string())?.get(salt) {
+        return Ok(ChaCha20Poly1305::new(Key::from_slice(key)));
+    }
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(&secret()?, salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {e}"))?;
+    keys.lock().map_err(|e| e.to_string())?.insert(*salt, key);
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// Seals `plaintext` with ChaCha20-Poly1305 as `enc:v1:<salt>:<nonce>:<ciphertext>`
+/// (hex), so a stored value carries everything but the secret to open it.
+pub fn encrypt(plaintext: &str) -> Result<String, String> {
+    let salt = WRITE_SALT.get_or_init(|| {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        salt
+    });
+    let cipher = derive(salt)?;
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok(format!("{PREFIX}{}:{}:{}", hex(salt), hex(&nonce), hex(&sealed)))
+}
+
+/// Opens a value written by [`encrypt`]; anything else is returned as is.
+fn decrypt(value: &str) -> Result<String, String> {
+    let Some(body) = value.strip_prefix(PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let malformed = || "Malformed encrypted password".to_string();
+    let mut parts = body.split(':');
+    let (Some(salt), Some(nonce), Some(sealed), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return Err(malformed());
+    };
+    let salt: [u8; 16] = unhex(salt).and_then(|s| s.try_into().ok()).ok_or_else(malformed)?;
+    let nonce: [u8; 12] = unhex(nonce).and_then(|n| n.try_into().ok()).ok_or_else(malformed)?;
+    let sealed = unhex(sealed).ok_or_else(malformed)?;
+    let opened = derive(&salt)?
+        .decrypt(Nonce::from_slice(&nonce), sealed.as_slice())
+        .map_err(|_| format!("Cannot decrypt the password: wrong key ({})", key_source().unwrap_or("none set")))?;
+    String::from_utf8(opened).map_err(|_| malformed())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
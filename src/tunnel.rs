@@ -0,0 +1,224 @@
+use anyhow::{anyhow, Result};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStdout, Command};
+use tracing::{info, warn};
+
+/// Printed by ssh once the forward is listening (`LocalCommand`).
+const READY: &str = "db-mcp-tunnel-ready";
+
+/// How long ssh may take to log in and set up the forward.
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Set in the environment of the ssh process when the key has a passphrase;
+/// ssh then runs this binary as its `SSH_ASKPASS` program.
+pub const ASKPASS_VAR: &str = "DB_MCP_ASKPASS_SECRET";
+
+/// SSH jump host a saved connection is reached through.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SshTunnel {
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    /// Login user; ssh's default (or `~/.ssh/config`) when empty.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub user: String,
+    /// Private key file. Without one, ssh uses the agent and its default keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Passphrase of `key`, stored like a password (encrypted, or an
+    /// `env:` / `file:` / `cmd:` source).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+impl SshTunnel {
+    /// `user@host:port`, for messages.
+    pub fn label(&self) -> String {
+        let user = if self.user.is_empty() { String::new() } else { format!("{}@", self.user) };
+        format!("{user}{}:{}", self.host, self.port)
+    }
+}
+
+/// A running `ssh -L` forward. Dropping it stops ssh.
+pub struct Tunnel {
+    child: Child,
+    _stdout: ChildStdout,
+    pub local_port: u16,
+    pub label: String,
+}
+
+impl Tunnel {
+    /// Points a connection URL at the local end of the forward, dropping a
+    /// socket path it may carry.
+    pub fn rewrite(&self, url: &str) -> Result<String> {
+        let mut parsed = url::Url::parse(url).map_err(|e| anyhow!("Invalid connection string: {e}"))?;
+        let rest: Vec<(String, String)> = parsed
+            .query_pairs()
+            .filter(|(k, _)| k != "host" && k != "socket")
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        if rest.is_empty() {
+            parsed.set_query(None);
+        } else {
+            parsed.query_pairs_mut().clear().extend_pairs(&rest);
+        }
+        parsed
+            .set_host(Some("127.0.0.1"))
+            .map_err(|e| anyhow!("Cannot route the connection through the tunnel: {e}"))?;
+        parsed
+            .set_port(Some(self.local_port))
+            .map_err(|_| anyhow!("Cannot route the connection through the tunnel"))?;
+        Ok(parsed.to_string())
+    }
+
+    pub async fn close(mut self) {
+        let _ = self.child.kill().await;
+        info!("Closed SSH tunnel via {}", self.label);
+    }
+}
+
+/// Refuses TLS modes that check the server's host name: through a tunnel the
+/// connection goes to `127.0.0.1`, which the certificate does not name.
+pub fn check_url(url: &str) -> Result<()> {
+    let parsed = url::Url::parse(url).map_err(|e| anyhow!("Invalid connection string: {e}"))?;
+    let verifies_host = parsed.query_pairs().any(|(k, v)| {
+        (k == "sslmode" && v.eq_ignore_ascii_case("verify-full"))
+            || (k == "ssl-mode" && v.eq_ignore_ascii_case("verify_identity"))
+    });
+    if verifies_host {
+        return Err(anyhow!(
+            "sslmode=verify-full cannot be used through an SSH tunnel: the connection goes to 127.0.0.1, \
+             which the server certificate does not name. Use sslmode=verify-ca to still check the certificate."
+        ));
+    }
+    Ok(())
+}
+
+/// Starts ssh with a local forward to `target_host:target_port` (as seen from
+/// the jump host), or to the Unix socket `target_host` names, and waits until
+/// it is listening. Runs `DB_MCP_SSH` if set, else `ssh` from `PATH`, so
+/// `~/.ssh/config`, `known_hosts` and the agent apply as they do on the
+/// command line.
+pub async fn open(ssh: &SshTunnel, target_host: &str, target_port: u16) -> Result<Tunnel> {
+    let local_port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    // A Unix socket on the far side is forwarded to by its path alone.
+    let target = if target_host.starts_with('/') {
+        target_host.to_string()
+    } else if target_host.contains(':') {
+        format!("[{target_host}]:{target_port}")
+    } else {
+        format!("{target_host}:{target_port}")
+    };
+    let program = std::env::var("DB_MCP_SSH").ok().filter(|p| !p.is_empty()).unwrap_or_else(|| "ssh".into());
+
+    let mut cmd = Command::new(&program);
+    cmd.args(["-N", "-T"])
+        .args(["-o", "ExitOnForwardFailure=yes"])
+        .args(["-o", "ServerAliveInterval=30"])
+        .args(["-o", "PermitLocalCommand=yes"])
+        .args(["-o", &format!("LocalCommand=echo {READY}")])
+        .args(["-L", &format!("127.0.0.1:{local_port}:{target}")])
+        .args(["-p", &ssh.port.to_string()]);
+    if !ssh.user.is_empty() {
+        cmd.args(["-l", &ssh.user]);
+    }
+    if let Some(key) = &ssh.key {
+        cmd.args(["-i", key, "-o", "IdentitiesOnly=yes"]);
+    }
+    match &ssh.passphrase {
+        // ssh asks this binary for the passphrase; see `askpass`.
+        Some(passphrase) => {
+            cmd.env("SSH_ASKPASS", std::env::current_exe()?)
+                .env("SSH_ASKPASS_REQUIRE", "force")
+                .env(ASKPASS_VAR, passphrase);
+        }
+        // Never prompt: there is no terminal to answer.
+        None => {
+            cmd.args(["-o", "BatchMode=yes"]);
+        }
+    }
+    cmd.arg("--")
+        .arg(&ssh.host)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn().map_err(|e| anyhow!("Cannot run '{program}': {e}"))?;
+    let label = ssh.label();
+
+    // Keep draining stderr so ssh never blocks on it; remember the last line
+    // for the error message.
+    let last_error = Arc::new(Mutex::new(String::new()));
+    if let Some(stderr) = child.stderr.take() {
+        let last_error = last_error.clone();
+        let label = label.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                warn!("ssh {label}: {line}");
+                if let Ok(mut last) = last_error.lock() {
+                    *last = line;
+                }
+            }
+        });
+    }
+
+    let mut stdout = BufReader::new(child.stdout.take().ok_or_else(|| anyhow!("ssh has no stdout"))?);
+    let ready = tokio::time::timeout(OPEN_TIMEOUT, async {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if stdout.read_line(&mut line).await? == 0 {
+                return Ok::<bool, std::io::Error>(false);
+            }
+            if line.trim() == READY {
+                return Ok(true);
+            }
+        }
+    })
+    .await;
+
+    match ready {
+        Ok(Ok(true)) => {
+            info!("SSH tunnel via {label}: 127.0.0.1:{local_port} -> {target}");
+            Ok(Tunnel { child, _stdout: stdout.into_inner(), local_port, label })
+        }
+        Ok(_) => {
+            let status = child.wait().await.ok();
+            // Give the stderr reader a moment to catch the final message.
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let reason = last_error.lock().map(|l| l.clone()).unwrap_or_default();
+            let reason = if reason.is_empty() {
+                status.map(|s| s.to_string()).unwrap_or_default()
+            } else {
+                reason
+            };
+            Err(anyhow!("SSH tunnel via {label} failed: {reason}"))
+        }
+        Err(_) => {
+            let _ = child.kill().await;
+            Err(anyhow!("SSH tunnel via {label} timed out after {}s", OPEN_TIMEOUT.as_secs()))
+        }
+    }
+}
+
+/// Run as ssh's `SSH_ASKPASS` program: answers a key passphrase prompt with
+/// the passphrase from the environment and refuses anything else, such as a
+/// host key confirmation.
+pub fn askpass(passphrase: &str) -> i32 {
+    let prompt = std::env::args().nth(1).unwrap_or_default();
+    if prompt.to_lowercase().contains("passphrase") {
+        println!("{passphrase}");
+        0
+    } else {
+        1
+    }
+}